@@ -0,0 +1,193 @@
+//! User access tokens expire after a few hours. When Helix rejects a token
+//! with `401 Unauthorized`, the client asks its [`TokenProvider`] for a new
+//! one, and sends the request again with it, once.
+//!
+//! A plain [`AccessToken`] cannot be refreshed, and the `401` is returned as
+//! it is. A [`RefreshingToken`] holds a refresh token too, and exchanges it
+//! for a new access token. If that fails, the user has to authorize the app
+//! again, and the client returns [`Error::ReauthorizationRequired`].
+//!
+//! Requests that are rejected at the same time share one refresh, so that a
+//! refresh token is never used twice.
+//!
+//! [`Error::ReauthorizationRequired`]: crate::client::Error::ReauthorizationRequired
+
+use std::error::Error as StdError;
+use std::future::Future;
+use std::sync::Mutex;
+
+use kekw_oauth2::requests::RefreshTokenRequestQuery;
+use kekw_oauth2::response::AuthTokenAllowed;
+use kekw_oauth2::types::{AccessToken, ClientId, ClientSecret, RefreshToken};
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum RefreshError {
+    #[error("the access token cannot be refreshed")]
+    Unsupported,
+    #[error("failed to refresh the access token")]
+    Failed(#[source] Box<dyn StdError + Send + Sync>),
+}
+
+/// Provides the access token for each request, and a new one when it is rejected.
+pub trait TokenProvider: Send + Sync {
+    /// The access token to send with the next request.
+    fn token(&self) -> AccessToken;
+
+    /// Replace the `rejected` token with a new one.
+    ///
+    /// If the token was already replaced since `rejected` was taken,
+    /// return the current token instead of refreshing again.
+    fn refresh(
+        &self,
+        rejected: &AccessToken,
+    ) -> impl Future<Output = Result<AccessToken, RefreshError>> + Send;
+}
+
+impl TokenProvider for AccessToken {
+    fn token(&self) -> AccessToken {
+        self.clone()
+    }
+
+    async fn refresh(&self, _rejected: &AccessToken) -> Result<AccessToken, RefreshError> {
+        Err(RefreshError::Unsupported)
+    }
+}
+
+/// A user access token, with the refresh token to replace it when it expires.
+///
+/// The `refresh` function sends a [`RefreshTokenRequestQuery`] with the HTTP
+/// client of your choice, and decodes the [`AuthTokenAllowed`] response.
+pub struct RefreshingToken<F> {
+    client_id: ClientId,
+    client_secret: ClientSecret,
+    tokens: Mutex<(AccessToken, RefreshToken)>,
+    /// Held while a refresh is in flight, so that concurrent refreshes wait for it.
+    refreshing: async_lock::Mutex<()>,
+    refresh: F,
+}
+
+impl<F, Fut, E> RefreshingToken<F>
+where
+    F: Fn(RefreshTokenRequestQuery) -> Fut,
+    Fut: Future<Output = Result<AuthTokenAllowed, E>>,
+{
+    pub fn new(
+        client_id: ClientId,
+        client_secret: ClientSecret,
+        access_token: AccessToken,
+        refresh_token: RefreshToken,
+        refresh: F,
+    ) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            tokens: Mutex::new((access_token, refresh_token)),
+            refreshing: async_lock::Mutex::new(()),
+            refresh,
+        }
+    }
+
+    /// The current refresh token, which changes when a refresh returns a new one.
+    /// Store this to resume the session later.
+    pub fn refresh_token(&self) -> RefreshToken {
+        self.tokens.lock().unwrap().1.clone()
+    }
+}
+
+impl<F, Fut, E> TokenProvider for RefreshingToken<F>
+where
+    F: Fn(RefreshTokenRequestQuery) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<AuthTokenAllowed, E>> + Send,
+    E: StdError + Send + Sync + 'static,
+{
+    fn token(&self) -> AccessToken {
+        self.tokens.lock().unwrap().0.clone()
+    }
+
+    async fn refresh(&self, rejected: &AccessToken) -> Result<AccessToken, RefreshError> {
+        let _refreshing = self.refreshing.lock().await;
+        let refresh_token = {
+            let tokens = self.tokens.lock().unwrap();
+            if tokens.0 != *rejected {
+                tracing::debug!("the access token was already refreshed");
+                return Ok(tokens.0.clone());
+            }
+            tokens.1.clone()
+        };
+
+        let query = RefreshTokenRequestQuery::builder()
+            .client_id(self.client_id.clone())
+            .client_secret(self.client_secret.clone())
+            .refresh_token(refresh_token)
+            .build();
+        tracing::info!("refreshing the access token");
+        let response = (self.refresh)(query).await.map_err(|error| {
+            // The error may include the request URL, which has the refresh token.
+            tracing::warn!("failed to refresh the access token");
+            RefreshError::Failed(Box::new(error))
+        })?;
+        tracing::info!(
+            expires_in = response.expires_in,
+            "refreshed the access token"
+        );
+
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.0 = response.access_token.clone();
+        // Twitch may keep the same refresh token, and omit it from the response.
+        if let Some(refresh_token) = response.refresh_token {
+            tokens.1 = refresh_token;
+        }
+        Ok(response.access_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use futures_lite::future::{block_on, yield_now, zip};
+
+    use super::*;
+
+    #[test]
+    fn test_concurrent_refresh() {
+        let calls = AtomicU32::new(0);
+        let token = RefreshingToken::new(
+            "wbmytr93xzw8zbg0p1izqyzzc5mbiz".into(),
+            "41vpdji4e9gif29md0ouet6fktd2".into(),
+            "expired".into(),
+            "eyJfMzUtNDU0OC4MWYwLTQ5MDY5ODY4NGNlMSJ9%asdfasdf=".into(),
+            |query: RefreshTokenRequestQuery| {
+                assert_eq!(
+                    query.refresh_token.expose_secret(),
+                    "eyJfMzUtNDU0OC4MWYwLTQ5MDY5ODY4NGNlMSJ9%asdfasdf="
+                );
+                calls.fetch_add(1, Ordering::SeqCst);
+                async {
+                    yield_now().await;
+                    Ok::<_, std::io::Error>(AuthTokenAllowed {
+                        access_token: "1ssjqsqfy6bads1ws7m03gras79zfr".into(),
+                        expires_in: 14346,
+                        refresh_token: Some("eyJfMdkyMS00ZjY5LTkxNDMtNGM0MjEzODJjMDY0In0=".into()),
+                        scope: Default::default(),
+                        token_type: "bearer".to_owned(),
+                    })
+                }
+            },
+        );
+
+        let rejected = token.token();
+        let (first, second) = block_on(zip(token.refresh(&rejected), token.refresh(&rejected)));
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            token.token().expose_secret(),
+            "1ssjqsqfy6bads1ws7m03gras79zfr"
+        );
+        assert_eq!(
+            token.refresh_token().expose_secret(),
+            "eyJfMdkyMS00ZjY5LTkxNDMtNGM0MjEzODJjMDY0In0="
+        );
+    }
+}
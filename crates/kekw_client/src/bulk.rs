@@ -0,0 +1,35 @@
+//! Endpoints such as Get Users accept at most 100 IDs or logins per request.
+//! [`HelixClient::send_bulk`] accepts any number of them, splits them into
+//! requests of that size, and sends those concurrently. Each request still
+//! waits for its [rate limit](crate::ratelimit) bucket, so a large lookup
+//! slows down rather than being rejected.
+//!
+//! The results are merged into a [`BulkResponse`], by the key that found them.
+//!
+//! [`HelixClient::send_bulk`]: crate::client::HelixClient::send_bulk
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The merged results of every request of a bulk lookup.
+#[derive(Clone, Debug)]
+pub struct BulkResponse<K, V> {
+    /// The result for each key that was found.
+    pub found: HashMap<K, V>,
+    /// The keys that were not found, in the order they were given.
+    pub missing: Vec<K>,
+}
+
+impl<K, V> BulkResponse<K, V>
+where
+    K: Eq + Hash,
+{
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.found.get(key)
+    }
+
+    /// Whether every key was found.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
@@ -0,0 +1,236 @@
+//! Badges, emotes, categories and user profiles rarely change. A
+//! [`ResponseCache`] keeps successful responses of the endpoints given a TTL,
+//! and [`HelixClient::send`] returns them until they expire, without a request.
+//!
+//! Responses are keyed by the method, the path, the query with its pairs in
+//! sorted order, and the access token. Responses for one token are never
+//! returned for another, because they may depend on the user of the token.
+//!
+//! Only `GET` endpoints are cached. When the cache holds too many entries,
+//! or too many bytes, the least recently used are evicted first.
+//!
+//! [`HelixClient::send`]: crate::client::HelixClient::send
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use kekw_endpoints::{Endpoint, Method};
+use kekw_oauth2::types::AccessToken;
+use lru::LruCache;
+use url::Url;
+
+use crate::transport::HttpResponse;
+
+/// How many responses to keep.
+pub const DEFAULT_MAX_ENTRIES: NonZeroUsize = match NonZeroUsize::new(1000) {
+    Some(size) => size,
+    None => unreachable!(),
+};
+/// How many bytes of response bodies to keep.
+pub const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    method: Method,
+    path: &'static str,
+    query: Vec<(String, String)>,
+    token: AccessToken,
+}
+
+struct Entry {
+    response: HttpResponse,
+    expires: Instant,
+}
+
+struct Inner {
+    ttls: HashMap<(Method, &'static str), Duration>,
+    entries: LruCache<CacheKey, Entry>,
+    max_bytes: usize,
+    bytes: usize,
+}
+
+impl Inner {
+    fn remove_where(&mut self, predicate: impl Fn(&CacheKey) -> bool) {
+        let keys = self
+            .entries
+            .iter()
+            .filter(|(key, _)| predicate(key))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in keys {
+            if let Some(entry) = self.entries.pop(&key) {
+                self.bytes -= entry.response.body.len();
+            }
+        }
+    }
+}
+
+/// Shared storage for the responses of the endpoints given a TTL.
+///
+/// Cloning this creates another handle to the same responses.
+#[derive(Clone)]
+pub struct ResponseCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES, DEFAULT_MAX_BYTES)
+    }
+}
+
+impl std::fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("ResponseCache")
+            .field("ttls", &inner.ttls)
+            .field("entries", &inner.entries.len())
+            .field("bytes", &inner.bytes)
+            .finish()
+    }
+}
+
+impl ResponseCache {
+    /// A cache that keeps at most `max_entries` responses, with bodies of
+    /// at most `max_bytes` in total. No endpoint is cached until it is given
+    /// a TTL with [`Self::ttl`].
+    pub fn new(max_entries: NonZeroUsize, max_bytes: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                ttls: HashMap::new(),
+                entries: LruCache::new(max_entries),
+                max_bytes,
+                bytes: 0,
+            })),
+        }
+    }
+
+    /// Cache the responses of the endpoint `R` for `ttl`.
+    pub fn ttl<R>(self, ttl: Duration) -> Self
+    where
+        R: Endpoint,
+    {
+        self.inner
+            .lock()
+            .unwrap()
+            .ttls
+            .insert((R::METHOD, R::PATH), ttl);
+        self
+    }
+
+    /// Remove every response of the endpoint `R`.
+    pub fn invalidate<R>(&self)
+    where
+        R: Endpoint,
+    {
+        self.inner
+            .lock()
+            .unwrap()
+            .remove_where(|key| key.method == R::METHOD && key.path == R::PATH);
+    }
+
+    /// Remove the responses to `request`, for every token.
+    pub fn invalidate_request<R>(&self, request: &R)
+    where
+        R: Endpoint,
+    {
+        let query = normalize(
+            request
+                .query_pairs()
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value)),
+        );
+        self.inner.lock().unwrap().remove_where(|key| {
+            key.method == R::METHOD && key.path == R::PATH && key.query == query
+        });
+    }
+
+    /// Remove every response.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.bytes = 0;
+    }
+
+    /// The number of responses, including those that expired but were not removed yet.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The response to the request `R` to `url` with `token`, if it has not expired.
+    pub(crate) fn get<R>(&self, url: &Url, token: &AccessToken) -> Option<HttpResponse>
+    where
+        R: Endpoint,
+    {
+        let mut inner = self.inner.lock().unwrap();
+        inner.ttls.get(&(R::METHOD, R::PATH))?;
+        let key = cache_key::<R>(url, token);
+        let entry = inner.entries.get(&key)?;
+        if entry.expires > Instant::now() {
+            return Some(entry.response.clone());
+        }
+        let entry = inner.entries.pop(&key)?;
+        inner.bytes -= entry.response.body.len();
+        None
+    }
+
+    /// Keep a successful response to the request `R`, if the endpoint has a TTL.
+    pub(crate) fn insert<R>(&self, url: &Url, token: &AccessToken, response: &HttpResponse)
+    where
+        R: Endpoint,
+    {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(&ttl) = inner.ttls.get(&(R::METHOD, R::PATH)) else {
+            return;
+        };
+        if R::METHOD != Method::Get
+            || !response.is_success()
+            || response.body.len() > inner.max_bytes
+        {
+            return;
+        }
+
+        let entry = Entry {
+            response: response.clone(),
+            expires: Instant::now() + ttl,
+        };
+        inner.bytes += entry.response.body.len();
+        if let Some((_, evicted)) = inner.entries.push(cache_key::<R>(url, token), entry) {
+            inner.bytes -= evicted.response.body.len();
+        }
+        while inner.bytes > inner.max_bytes {
+            let Some((_, evicted)) = inner.entries.pop_lru() else {
+                break;
+            };
+            inner.bytes -= evicted.response.body.len();
+        }
+    }
+}
+
+fn cache_key<R>(url: &Url, token: &AccessToken) -> CacheKey
+where
+    R: Endpoint,
+{
+    CacheKey {
+        method: R::METHOD,
+        path: R::PATH,
+        query: normalize(
+            url.query_pairs()
+                .map(|(key, value)| (key.into_owned(), value.into_owned())),
+        ),
+        token: token.clone(),
+    }
+}
+
+/// Sort the pairs, so that the same query in another order has the same key.
+fn normalize(pairs: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    let mut pairs = pairs.collect::<Vec<_>>();
+    pairs.sort();
+    pairs
+}
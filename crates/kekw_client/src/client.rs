@@ -0,0 +1,967 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use async_io::Timer;
+use futures_util::future::try_join_all;
+use kekw_endpoints::{BulkLookup, Endpoint, HelixResponse, Paginated, HELIX_BASE_URL};
+use kekw_oauth2::types::{AccessToken, ClientId};
+use serde::Deserialize;
+use tracing::Instrument as _;
+use typed_builder::TypedBuilder;
+use url::Url;
+
+use crate::auth::{RefreshError, TokenProvider};
+use crate::bulk::BulkResponse;
+use crate::cache::ResponseCache;
+use crate::paginate::Paginator;
+use crate::ratelimit::{RateLimit, RateLimits};
+use crate::retry::{GiveUp, RetryPolicy};
+use crate::tokens::{self, TokenSource};
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+
+/// How many times a request is sent again after `429 Too Many Requests`.
+pub const DEFAULT_RATE_LIMIT_RETRIES: u32 = 3;
+/// How long to wait after `429 Too Many Requests` without `Ratelimit-*` headers.
+pub const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error<E> {
+    #[error("failed to send request")]
+    Transport(#[source] E),
+    #[error("failed to build request URL")]
+    Url(#[from] url::ParseError),
+    #[error("failed to serialize request body")]
+    Body(#[source] serde_json::Error),
+    #[error("failed to decode response body")]
+    Decode(#[source] serde_json::Error),
+    #[error("Helix responded with {status} {error}: {message}")]
+    Status {
+        status: u16,
+        error: String,
+        message: String,
+    },
+    /// The request failed in a way that could be transient, and was not retried again.
+    #[error("request failed after {attempts} attempts: {reason}")]
+    GaveUp {
+        /// The attempts that failed in a transient way, which the [`RetryPolicy`]
+        /// counts. Replays after a refresh or a `429` are not included.
+        attempts: u32,
+        reason: GiveUp,
+        #[source]
+        last: Box<Error<E>>,
+    },
+    /// The access token was rejected, and could not be refreshed.
+    /// The user must authorize the app again.
+    #[error("the access token was rejected, and could not be refreshed")]
+    ReauthorizationRequired(#[source] RefreshError),
+    #[error("no suitable access token")]
+    Token(#[from] tokens::Error),
+}
+
+/// The body of a response with an error status.
+#[derive(Deserialize)]
+struct HelixError {
+    #[serde(default)]
+    error: String,
+    #[serde(default)]
+    message: String,
+}
+
+/// Sends [`Endpoint`] requests with a [`Transport`], and decodes the responses.
+///
+/// Every request has the `Client-Id` header, and an `Authorization` header
+/// with the access token as a bearer token.
+///
+/// Requests wait for the [rate limit](crate::ratelimit) bucket of the client ID
+/// and token to refill when it is empty. Responses with `429 Too Many Requests`
+/// are retried after the bucket resets, up to `rate_limit_retries` times.
+///
+/// Transient failures are retried according to the [`RetryPolicy`].
+///
+/// When Helix responds with `401 Unauthorized`, the token is
+/// [refreshed](crate::auth) and the request is sent again, once.
+///
+/// With a [`TokenRouter`](crate::tokens::TokenRouter), each request gets the
+/// app token or the token of the user it requires.
+///
+/// With a [`ResponseCache`], responses of the endpoints it has a TTL for
+/// are returned from it until they expire.
+#[derive(TypedBuilder)]
+pub struct HelixClient<T, A = AccessToken> {
+    transport: T,
+    #[builder(setter(into))]
+    client_id: ClientId,
+    /// An [`AccessToken`], a [`TokenProvider`] that can refresh it,
+    /// or a [`TokenSource`] that picks one per request.
+    token: A,
+    /// Override to send requests to a mock server.
+    #[builder(default = Url::parse(HELIX_BASE_URL).unwrap())]
+    base_url: Url,
+    /// Share this between clients with the same credentials.
+    #[builder(default)]
+    rate_limits: RateLimits,
+    #[builder(default = DEFAULT_RATE_LIMIT_RETRIES)]
+    rate_limit_retries: u32,
+    #[builder(default)]
+    retry_policy: RetryPolicy,
+    #[builder(default, setter(strip_option))]
+    cache: Option<ResponseCache>,
+}
+
+impl<T> HelixClient<T, AccessToken> {
+    /// Replace the access token used for subsequent requests.
+    pub fn set_token(&mut self, token: AccessToken) {
+        self.token = token;
+    }
+}
+
+impl<T, A> HelixClient<T, A>
+where
+    T: Transport,
+    A: TokenProvider,
+{
+    /// The state of the rate limit bucket for the current token.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limits.get(&self.client_id, &self.token.token())
+    }
+}
+
+impl<T, A> HelixClient<T, A>
+where
+    T: Transport,
+    A: TokenSource,
+{
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn client_id(&self) -> &ClientId {
+        &self.client_id
+    }
+
+    pub fn token(&self) -> &A {
+        &self.token
+    }
+
+    /// Add or remove tokens of a [`TokenRouter`](crate::tokens::TokenRouter).
+    pub fn token_mut(&mut self) -> &mut A {
+        &mut self.token
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    pub fn rate_limits(&self) -> &RateLimits {
+        &self.rate_limits
+    }
+
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
+    }
+
+    /// Send a request, and decode the `data`, `pagination` and `total` of the response.
+    pub async fn send<R>(&self, request: &R) -> Result<HelixResponse<R::Response>, Error<T::Error>>
+    where
+        R: Endpoint,
+    {
+        let span = tracing::debug_span!("helix_request", method = %R::METHOD, path = R::PATH);
+        self.send_attempts(request).instrument(span).await
+    }
+
+    async fn send_attempts<R>(
+        &self,
+        request: &R,
+    ) -> Result<HelixResponse<R::Response>, Error<T::Error>>
+    where
+        R: Endpoint,
+    {
+        let provider = self.token.select(request)?;
+        let mut token = provider.token();
+        let mut http_request = self.authorized_request(request, &token)?;
+        if let Some(response) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get::<R>(&http_request.url, &token))
+        {
+            tracing::debug!("returned a cached response");
+            return decode_response(response);
+        }
+        // Every request sent, and those that failed in a way the retry policy counts.
+        let mut attempts = 0;
+        let mut failures = 0;
+        let mut rate_limit_retries = 0;
+        let mut refreshed = false;
+        loop {
+            self.rate_limits.acquire(&self.client_id, &token).await;
+            attempts += 1;
+            tracing::debug!(attempt = attempts, "sending request");
+            let (error, network_error) = match self.transport.send(http_request.clone()).await {
+                Ok(response) => {
+                    tracing::debug!(status = response.status, "received response");
+                    let bucket = self.rate_limits.update(&self.client_id, &token, &response);
+                    if response.status == 401 && !refreshed {
+                        refreshed = true;
+                        match provider.refresh(&token).await {
+                            Ok(new_token) => token = new_token,
+                            Err(RefreshError::Unsupported) => break decode_response(response),
+                            Err(error) => {
+                                tracing::warn!(%error, "the access token was rejected, and could not be refreshed");
+                                break Err(Error::ReauthorizationRequired(error));
+                            }
+                        }
+                        http_request = self.authorized_request(request, &token)?;
+                        continue;
+                    }
+                    if response.status == 429 && rate_limit_retries < self.rate_limit_retries {
+                        rate_limit_retries += 1;
+                        tracing::warn!(
+                            retry = rate_limit_retries,
+                            "rate limit exceeded, retrying after the bucket resets"
+                        );
+                        // With headers, the bucket is empty, and the next `acquire` waits.
+                        if bucket.is_none() {
+                            Timer::after(DEFAULT_RATE_LIMIT_WAIT).await;
+                        }
+                        continue;
+                    }
+                    if !self.retry_policy.is_transient(response.status) {
+                        if let Some(cache) = &self.cache {
+                            cache.insert::<R>(&http_request.url, &token, &response);
+                        }
+                        break decode_response(response);
+                    }
+                    (status_error(&response), false)
+                }
+                Err(error) => (Error::Transport(error), true),
+            };
+            failures += 1;
+            match self.retry_policy.decide::<R>(failures, network_error) {
+                Ok(backoff) => {
+                    tracing::warn!(attempt = failures, %error, ?backoff, "request failed, retrying");
+                    Timer::after(backoff).await;
+                }
+                Err(reason) => {
+                    tracing::warn!(attempts = failures, %error, %reason, "request failed, giving up");
+                    break Err(Error::GaveUp {
+                        attempts: failures,
+                        reason,
+                        last: Box::new(error),
+                    });
+                }
+            };
+        }
+    }
+
+    /// Send a paginated request repeatedly, following the cursor of each page,
+    /// and stream the items of every page.
+    pub fn paginate<'a, R>(&'a self, request: R) -> Paginator<'a, T, A, R>
+    where
+        R: Paginated + Clone + 'a,
+        R::Response: IntoIterator,
+    {
+        Paginator::new(self, request)
+    }
+
+    /// Look up any number of keys, with as many requests as [`BulkLookup::MAX_KEYS`]
+    /// requires, sent concurrently. Duplicate keys are looked up once.
+    ///
+    /// Fails with the first error of any request. Nothing is sent without keys.
+    pub async fn send_bulk<R>(
+        &self,
+        request: &R,
+    ) -> Result<BulkResponse<R::Key, R::Item>, Error<T::Error>>
+    where
+        R: BulkLookup,
+        R::Response: IntoIterator<Item = R::Item>,
+        R::Item: Clone,
+    {
+        let mut requested = HashSet::new();
+        let keys = request
+            .keys()
+            .into_iter()
+            .filter(|key| requested.insert(key.clone()))
+            .collect::<Vec<_>>();
+        let batches = keys
+            .chunks(R::MAX_KEYS)
+            .map(|keys| request.with_keys(keys))
+            .collect::<Vec<_>>();
+        tracing::debug!(
+            keys = keys.len(),
+            requests = batches.len(),
+            "sending bulk lookup"
+        );
+        let responses = try_join_all(batches.iter().map(|batch| self.send(batch))).await?;
+
+        let mut found = HashMap::new();
+        for item in responses.into_iter().flat_map(|response| response.data) {
+            for key in R::item_keys(&item) {
+                if requested.contains(&key) {
+                    found.insert(key, item.clone());
+                }
+            }
+        }
+        let missing = keys
+            .into_iter()
+            .filter(|key| !found.contains_key(key))
+            .collect();
+        Ok(BulkResponse { found, missing })
+    }
+
+    /// Build the request that [`Self::send`] would send with the current token.
+    pub fn http_request<R>(&self, request: &R) -> Result<HttpRequest, Error<T::Error>>
+    where
+        R: Endpoint,
+    {
+        self.authorized_request(request, &self.token.select(request)?.token())
+    }
+
+    fn authorized_request<R>(
+        &self,
+        request: &R,
+        token: &AccessToken,
+    ) -> Result<HttpRequest, Error<T::Error>>
+    where
+        R: Endpoint,
+    {
+        let mut url = Url::parse(&format!(
+            "{}{}",
+            self.base_url.as_str().trim_end_matches('/'),
+            R::PATH
+        ))?;
+        let pairs = request.query_pairs();
+        if !pairs.is_empty() {
+            url.query_pairs_mut().extend_pairs(pairs);
+        }
+
+        let body = request.body().map_err(Error::Body)?;
+
+        let mut headers = vec![
+            ("Client-Id", self.client_id.as_str().to_owned()),
+            ("Authorization", format!("Bearer {}", token.expose_secret())),
+        ];
+        if body.is_some() {
+            headers.push(("Content-Type", "application/json".to_owned()));
+        }
+
+        Ok(HttpRequest {
+            method: R::METHOD,
+            url,
+            headers,
+            body,
+        })
+    }
+}
+
+fn decode_response<T, E>(response: HttpResponse) -> Result<HelixResponse<T>, Error<E>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if !response.is_success() {
+        return Err(status_error(&response));
+    }
+    // Endpoints that respond with `204 No Content` have no `data`.
+    let body = if response.body.is_empty() {
+        br#"{"data":null}"#.as_slice()
+    } else {
+        &response.body
+    };
+    serde_json::from_slice(body).map_err(Error::Decode)
+}
+
+fn status_error<E>(response: &HttpResponse) -> Error<E> {
+    let HelixError { error, message } =
+        serde_json::from_slice(&response.body).unwrap_or_else(|_| HelixError {
+            error: String::new(),
+            message: String::from_utf8_lossy(&response.body).into_owned(),
+        });
+    Error::Status {
+        status: response.status,
+        error,
+        message,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::num::NonZeroUsize;
+    use std::sync::{Arc, Mutex};
+    use std::time::SystemTime;
+
+    use futures_lite::future::block_on;
+    use futures_lite::StreamExt;
+    use kekw_endpoints::chat::GetChatters;
+    use kekw_endpoints::streams::GetStreams;
+    use kekw_endpoints::users::{GetUsers, UserKey};
+    use kekw_oauth2::response::AuthTokenAllowed;
+    use kekw_oauth2::types::Scope;
+    use kekw_oauth2::validation::{TokenValidator, ValidatedTokens, Validation};
+
+    use super::*;
+    use crate::auth::RefreshingToken;
+    use crate::tokens::{TokenRouter, Validated};
+
+    /// Responds with each response in turn, repeating the last one.
+    #[derive(Default)]
+    pub(crate) struct MockTransport {
+        pub(crate) requests: Mutex<Vec<HttpRequest>>,
+        pub(crate) responses: Mutex<VecDeque<HttpResponse>>,
+    }
+
+    impl Transport for MockTransport {
+        type Error = Infallible;
+
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Self::Error> {
+            self.requests.lock().unwrap().push(request);
+            let mut responses = self.responses.lock().unwrap();
+            if responses.len() > 1 {
+                Ok(responses.pop_front().unwrap())
+            } else {
+                Ok(responses[0].clone())
+            }
+        }
+    }
+
+    pub(crate) fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            body: body.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn client(status: u16, body: &str) -> HelixClient<MockTransport> {
+        client_with(vec![response(status, body)])
+    }
+
+    pub(crate) fn client_with(responses: Vec<HttpResponse>) -> HelixClient<MockTransport> {
+        HelixClient::builder()
+            .transport(MockTransport {
+                responses: Mutex::new(responses.into()),
+                ..Default::default()
+            })
+            .client_id("wbmytr93xzw8zbg0p1izqyzzc5mbiz")
+            .token(AccessToken::from("2gbdx6oar67tqtcmt49t3wpcgycthx"))
+            .base_url(Url::parse("http://localhost:8080/mock/").unwrap())
+            .build()
+    }
+
+    #[test]
+    fn test_send_request() {
+        let client = client(
+            200,
+            r#"{
+                "data": [{ "user_id": "128393656", "user_login": "smittysmithers", "user_name": "smittysmithers" }],
+                "pagination": { "cursor": "eyJiIjpudWxsLCJhIjp7Ik9mZnNldCI6NX19" },
+                "total": 8
+            }"#,
+        );
+        let request = GetChatters::builder()
+            .broadcaster_id("123456")
+            .moderator_id("654321")
+            .build();
+        let response = block_on(client.send(&request)).unwrap();
+        assert_eq!(response.data[0].user_login, "smittysmithers");
+        assert_eq!(response.total, Some(8));
+        assert!(response.pagination.cursor.is_some());
+
+        let sent = client.transport().requests.lock().unwrap().remove(0);
+        assert_eq!(
+            sent.url.as_str(),
+            "http://localhost:8080/mock/helix/chat/chatters?broadcaster_id=123456&moderator_id=654321"
+        );
+        assert_eq!(
+            sent.headers,
+            vec![
+                ("Client-Id", "wbmytr93xzw8zbg0p1izqyzzc5mbiz".to_owned()),
+                (
+                    "Authorization",
+                    "Bearer 2gbdx6oar67tqtcmt49t3wpcgycthx".to_owned()
+                ),
+            ]
+        );
+        assert!(!format!("{sent:?}").contains("2gbdx6oar67tqtcmt49t3wpcgycthx"));
+    }
+
+    #[test]
+    fn test_error_status() {
+        let client = client(
+            401,
+            r#"{ "error": "Unauthorized", "status": 401, "message": "Invalid OAuth token" }"#,
+        );
+        let error = block_on(client.send(&GetUsers::default())).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Status { status: 401, ref message, .. } if message == "Invalid OAuth token"
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit_retry() {
+        let reset = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 1;
+        let headers = |remaining: u32| {
+            vec![
+                ("Ratelimit-Limit".to_owned(), "800".to_owned()),
+                ("Ratelimit-Remaining".to_owned(), remaining.to_string()),
+                ("Ratelimit-Reset".to_owned(), reset.to_string()),
+            ]
+        };
+        let client = client_with(vec![
+            HttpResponse {
+                headers: headers(0),
+                ..response(429, r#"{ "error": "Too Many Requests", "status": 429 }"#)
+            },
+            HttpResponse {
+                headers: headers(799),
+                ..response(200, r#"{ "data": [] }"#)
+            },
+        ]);
+        assert_eq!(client.rate_limit(), None);
+
+        let response = block_on(client.send(&GetUsers::default())).unwrap();
+        assert!(response.data.is_empty());
+        assert_eq!(client.transport().requests.lock().unwrap().len(), 2);
+
+        let bucket = client.rate_limit().unwrap();
+        assert_eq!((bucket.limit, bucket.remaining), (800, 799));
+        assert_eq!(client.rate_limits().buckets().len(), 1);
+    }
+
+    #[test]
+    fn test_refresh_token() {
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_max_level(tracing::Level::TRACE)
+                .with_writer({
+                    let logs = logs.clone();
+                    move || LogWriter(logs.clone())
+                })
+                .finish(),
+        );
+
+        let refreshing = |succeed: bool| {
+            RefreshingToken::new(
+                "wbmytr93xzw8zbg0p1izqyzzc5mbiz".into(),
+                "41vpdji4e9gif29md0ouet6fktd2".into(),
+                "2gbdx6oar67tqtcmt49t3wpcgycthx".into(),
+                "eyJfMzUtNDU0OC4MWYwLTQ5MDY5ODY4NGNlMSJ9%asdfasdf=".into(),
+                move |_| async move {
+                    if !succeed {
+                        return Err(std::io::Error::other("Invalid refresh token"));
+                    }
+                    Ok(AuthTokenAllowed {
+                        access_token: "1ssjqsqfy6bads1ws7m03gras79zfr".into(),
+                        expires_in: 14346,
+                        refresh_token: None,
+                        scope: Default::default(),
+                        token_type: "bearer".to_owned(),
+                    })
+                },
+            )
+        };
+        let client_with_token = |token| {
+            let client = client_with(vec![
+                response(
+                    401,
+                    r#"{ "error": "Unauthorized", "status": 401, "message": "Invalid OAuth token" }"#,
+                ),
+                response(200, r#"{ "data": [] }"#),
+            ]);
+            HelixClient::builder()
+                .transport(client.transport)
+                .client_id(client.client_id)
+                .token(token)
+                .base_url(client.base_url)
+                .build()
+        };
+
+        let client = client_with_token(refreshing(true));
+        assert!(block_on(client.send(&GetUsers::default())).is_ok());
+        let requests = client.transport().requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1].headers[1].1,
+            "Bearer 1ssjqsqfy6bads1ws7m03gras79zfr"
+        );
+        drop(requests);
+
+        let client = client_with_token(refreshing(false));
+        let error = block_on(client.send(&GetUsers::default())).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::ReauthorizationRequired(RefreshError::Failed(_))
+        ));
+        assert_eq!(client.transport().requests.lock().unwrap().len(), 1);
+
+        let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("refreshed the access token"));
+        for secret in [
+            "41vpdji4e9gif29md0ouet6fktd2",
+            "2gbdx6oar67tqtcmt49t3wpcgycthx",
+            "eyJfMzUtNDU0OC4MWYwLTQ5MDY5ODY4NGNlMSJ9",
+            "1ssjqsqfy6bads1ws7m03gras79zfr",
+        ] {
+            assert!(!logs.contains(secret));
+        }
+    }
+
+    #[test]
+    fn test_revoked_token() {
+        let token = AccessToken::from("2gbdx6oar67tqtcmt49t3wpcgycthx");
+        let tokens = ValidatedTokens::new();
+        tokens.insert("app", token.clone());
+        let client = client(200, r#"{ "data": [] }"#);
+        let client = HelixClient::builder()
+            .transport(client.transport)
+            .client_id(client.client_id)
+            .token(Validated::new(token, tokens.clone()))
+            .base_url(client.base_url)
+            .build();
+        block_on(client.send(&GetUsers::default())).unwrap();
+
+        let validator = TokenValidator::builder()
+            .tokens(tokens)
+            .validate(|_| async { Ok::<_, Infallible>(Validation::Invalid) })
+            .build();
+        block_on(validator.run().take(1).collect::<Vec<_>>());
+
+        let error = block_on(client.send(&GetUsers::default())).unwrap_err();
+        assert!(matches!(error, Error::Token(tokens::Error::Revoked { .. })));
+        assert_eq!(client.transport().requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_token_router() {
+        let mut router = TokenRouter::new();
+        router.set_app(AccessToken::from("2gbdx6oar67tqtcmt49t3wpcgycthx"));
+        let client = client(200, r#"{ "data": [] }"#);
+        let mut client = HelixClient::builder()
+            .transport(client.transport)
+            .client_id(client.client_id)
+            .token(router)
+            .base_url(client.base_url)
+            .build();
+        let request = GetChatters::builder()
+            .broadcaster_id("123456")
+            .moderator_id("654321")
+            .build();
+
+        let error = block_on(client.send(&request)).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Token(tokens::Error::NoUserToken { .. })
+        ));
+        assert!(client.transport().requests.lock().unwrap().is_empty());
+
+        client.token_mut().insert_user(
+            "654321",
+            AccessToken::from("1ssjqsqfy6bads1ws7m03gras79zfr"),
+            Scope::ModeratorReadChatters.into(),
+        );
+        block_on(client.send(&request)).unwrap();
+        assert_eq!(
+            client.transport().requests.lock().unwrap()[0].headers[1].1,
+            "Bearer 1ssjqsqfy6bads1ws7m03gras79zfr"
+        );
+    }
+
+    /// Collects the output of a `tracing` subscriber.
+    struct LogWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_paginate() {
+        let page = |logins: &[&str], cursor: &str| {
+            let data = logins
+                .iter()
+                .map(|login| {
+                    format!(
+                        r#"{{ "user_id": "1", "user_login": "{login}", "user_name": "{login}" }}"#
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            response(
+                200,
+                &format!(r#"{{ "data": [{data}], "pagination": {{ "cursor": "{cursor}" }} }}"#),
+            )
+        };
+        let pages = || vec![page(&["a", "b"], "eyJiIjpudWxsfQ"), page(&["c"], "")];
+        let request = GetChatters::builder()
+            .broadcaster_id("123456")
+            .moderator_id("654321")
+            .build();
+        let logins = |paginator: Paginator<'_, _, _, GetChatters>| {
+            block_on(
+                paginator
+                    .map(|chatter| chatter.unwrap().user_login)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let client = client_with(pages());
+        assert_eq!(logins(client.paginate(request.clone())), ["a", "b", "c"]);
+        let requests = client.transport().requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].url.as_str().ends_with("&after=eyJiIjpudWxsfQ"));
+        drop(requests);
+
+        let client = client_with(pages());
+        assert_eq!(
+            logins(client.paginate(request.clone()).limit(2)),
+            ["a", "b"]
+        );
+        assert_eq!(client.transport().requests.lock().unwrap().len(), 1);
+
+        let client = client_with(pages());
+        let mut paginator = client
+            .paginate(request)
+            .page_size(2)
+            .resume("eyJiIjpudWxsfQ");
+        assert_eq!(paginator.cursor(), Some("eyJiIjpudWxsfQ"));
+        block_on(paginator.next()).unwrap().unwrap();
+        assert_eq!(paginator.cursor(), Some("eyJiIjpudWxsfQ"));
+        assert_eq!(paginator.buffered(), 1);
+        let requests = client.transport().requests.lock().unwrap();
+        assert!(requests[0]
+            .url
+            .as_str()
+            .ends_with("&first=2&after=eyJiIjpudWxsfQ"));
+    }
+
+    #[test]
+    fn test_send_bulk() {
+        let client = client(
+            200,
+            r#"{
+                "data": [{
+                    "id": "141981764",
+                    "login": "twitchdev",
+                    "display_name": "TwitchDev",
+                    "type": "",
+                    "broadcaster_type": "partner",
+                    "description": "Supporting third-party developers building Twitch integrations.",
+                    "profile_image_url": "",
+                    "offline_image_url": "",
+                    "created_at": "2016-12-14T20:32:28Z"
+                }]
+            }"#,
+        );
+        let ids = (141981700..141981850).map(|id| id.to_string()).collect();
+        let request = GetUsers::builder()
+            .id(ids)
+            .login(vec!["TwitchDev".to_owned(), "twitchdev".to_owned()])
+            .build();
+        let response = block_on(client.send_bulk(&request)).unwrap();
+
+        let requests = client.transport().requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let counts = requests
+            .iter()
+            .map(|request| request.url.query_pairs().count())
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [100, 51]);
+        assert_eq!(response.found.len(), 2);
+        assert_eq!(
+            response
+                .get(&UserKey::Id("141981764".to_owned()))
+                .unwrap()
+                .login,
+            "twitchdev"
+        );
+        assert!(response
+            .get(&UserKey::Login("twitchdev".to_owned()))
+            .is_some());
+        assert_eq!(response.missing.len(), 149);
+        assert_eq!(response.missing[0], UserKey::Id("141981700".to_owned()));
+    }
+
+    #[test]
+    fn test_send_bulk_streams() {
+        let client = client(
+            200,
+            r#"{
+                "data": [{
+                    "id": "40952121085",
+                    "user_id": "101051819",
+                    "user_login": "afro",
+                    "user_name": "Afro",
+                    "game_id": "32982",
+                    "game_name": "Grand Theft Auto V",
+                    "type": "live",
+                    "title": "Jacob: Digital Den Laptops & Routers",
+                    "tags": ["English"],
+                    "viewer_count": 1490,
+                    "started_at": "2021-03-10T03:18:11Z",
+                    "language": "en",
+                    "thumbnail_url": "https://static-cdn.jtvnw.net/previews-ttv/live_user_afro-{width}x{height}.jpg",
+                    "is_mature": false
+                }],
+                "pagination": { "cursor": "eyJiIjp7IkN1cnNvciI6ImV5SnpJam8" }
+            }"#,
+        );
+        let ids = (101051800..101051920).map(|id| id.to_string()).collect();
+        let request = GetStreams::builder()
+            .user_id(ids)
+            .language(vec!["en".to_owned()])
+            .build();
+        let response = block_on(client.send_bulk(&request)).unwrap();
+
+        let requests = client.transport().requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let firsts = requests
+            .iter()
+            .map(|request| {
+                let pairs = request.url.query_pairs().collect::<Vec<_>>();
+                assert!(pairs.contains(&("language".into(), "en".into())));
+                pairs.into_iter().find(|(key, _)| key == "first").unwrap().1
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(firsts, ["100", "20"]);
+        assert_eq!(response.found.len(), 1);
+        assert_eq!(
+            response
+                .get(&UserKey::Id("101051819".to_owned()))
+                .unwrap()
+                .user_login,
+            "afro"
+        );
+        assert_eq!(response.missing.len(), 119);
+    }
+
+    #[test]
+    fn test_response_cache() {
+        let mut client = client(200, r#"{ "data": [] }"#);
+        client.cache = Some(
+            ResponseCache::new(NonZeroUsize::new(2).unwrap(), 1024)
+                .ttl::<GetUsers>(Duration::from_secs(60)),
+        );
+        let sent =
+            |client: &HelixClient<MockTransport>| client.transport().requests.lock().unwrap().len();
+        let users = |ids: &[&str]| {
+            GetUsers::builder()
+                .id(ids.iter().map(|&id| id.to_owned()).collect())
+                .build()
+        };
+
+        block_on(client.send(&users(&["1", "2"]))).unwrap();
+        block_on(client.send(&users(&["1", "2"]))).unwrap();
+        assert_eq!(sent(&client), 1);
+
+        // Another token never gets the responses of the first.
+        client.set_token(AccessToken::from("1ssjqsqfy6bads1ws7m03gras79zfr"));
+        block_on(client.send(&users(&["1", "2"]))).unwrap();
+        assert_eq!(sent(&client), 2);
+
+        // The least recently used response is evicted.
+        block_on(client.send(&users(&["3"]))).unwrap();
+        assert_eq!(client.cache().unwrap().len(), 2);
+
+        client
+            .cache()
+            .unwrap()
+            .invalidate_request(&users(&["1", "2"]));
+        assert_eq!(client.cache().unwrap().len(), 1);
+        block_on(client.send(&users(&["1", "2"]))).unwrap();
+        assert_eq!(sent(&client), 4);
+
+        // Endpoints without a TTL are not cached.
+        let chatters = GetChatters::builder()
+            .broadcaster_id("123456")
+            .moderator_id("654321")
+            .build();
+        block_on(client.send(&chatters)).unwrap();
+        assert_eq!(client.cache().unwrap().len(), 2);
+        client.cache().unwrap().invalidate::<GetUsers>();
+        assert!(client.cache().unwrap().is_empty());
+    }
+
+    #[derive(kekw_macros::Endpoint)]
+    #[endpoint(POST, "/helix/test", response = ())]
+    struct PostTest;
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::builder()
+            .initial_backoff(Duration::ZERO)
+            .build();
+        let unavailable = || response(503, r#"{ "error": "Service Unavailable", "status": 503 }"#);
+
+        let mut client = client_with(vec![unavailable(), response(200, r#"{ "data": [] }"#)]);
+        client.retry_policy = policy.clone();
+        assert!(block_on(client.send(&GetUsers::default())).is_ok());
+        assert_eq!(client.transport().requests.lock().unwrap().len(), 2);
+
+        let mut client = client_with(vec![unavailable()]);
+        client.retry_policy = policy.clone();
+        let error = block_on(client.send(&PostTest)).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::GaveUp {
+                attempts: 1,
+                reason: GiveUp::NotIdempotent,
+                ..
+            }
+        ));
+
+        let mut client = client_with(vec![unavailable()]);
+        client.retry_policy = policy.allow::<PostTest>();
+        let error = block_on(client.send(&PostTest)).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::GaveUp {
+                attempts: 4,
+                reason: GiveUp::Exhausted,
+                ref last,
+            } if matches!(**last, Error::Status { status: 503, .. })
+        ));
+
+        // A `429` is retried separately, and does not use up the policy.
+        let reset = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 1;
+        let too_many_requests = HttpResponse {
+            headers: vec![
+                ("Ratelimit-Limit".to_owned(), "800".to_owned()),
+                ("Ratelimit-Remaining".to_owned(), "0".to_owned()),
+                ("Ratelimit-Reset".to_owned(), reset.to_string()),
+            ],
+            ..response(429, r#"{ "error": "Too Many Requests", "status": 429 }"#)
+        };
+        let mut client = client_with(vec![too_many_requests, unavailable()]);
+        client.retry_policy = RetryPolicy::builder()
+            .initial_backoff(Duration::ZERO)
+            .max_retries(1)
+            .build();
+        let error = block_on(client.send(&GetUsers::default())).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::GaveUp {
+                attempts: 2,
+                reason: GiveUp::Exhausted,
+                ..
+            }
+        ));
+        assert_eq!(client.transport().requests.lock().unwrap().len(), 3);
+    }
+}
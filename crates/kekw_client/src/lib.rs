@@ -1,14 +1,30 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+/// Provide the access token for each request, and refresh it when it expires.
+pub mod auth;
+
+/// Look up any number of IDs or logins with endpoints that accept a few per request.
+pub mod bulk;
+
+/// Keep the responses of endpoints that rarely change, for a time.
+pub mod cache;
+
+/// Send requests to the Helix API, authenticated with an access token.
+pub mod client;
+
+/// Coalesce concurrent lookups into bulk requests, and cache the results.
+pub mod loader;
+
+/// Stream the items of every page of a paginated endpoint.
+pub mod paginate;
+
+/// Track the rate limit buckets of the Helix API, and wait for them to refill.
+pub mod ratelimit;
+
+/// Decide whether to retry requests that failed in transient ways.
+pub mod retry;
+
+/// Hold an app token and many user tokens, and pick one for each request.
+pub mod tokens;
+
+/// The HTTP requests and responses that a client sends and receives, and the
+/// trait to implement for the HTTP client of your choice.
+pub mod transport;
@@ -0,0 +1,265 @@
+//! Many tasks that each look up a user with its own request would quickly
+//! use up the rate limit. A [`Loader`] collects the keys that are loaded within
+//! a short window, and looks all of them up with one [bulk](crate::bulk)
+//! request. Every caller waiting for a key of that window gets its result.
+//!
+//! Results are kept in a bounded cache, and the least recently used are
+//! evicted first, so frequently loaded keys are only requested once.
+//! Give the loader a `ttl` to request them again once they are that old.
+//!
+//! Nothing is spawned. The first caller of each window waits for it to close,
+//! and sends the request. If that caller is cancelled, the next caller
+//! waiting for the same window sends it instead.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_io::Timer;
+use async_lock::OnceCell;
+use kekw_endpoints::BulkLookup;
+use lru::LruCache;
+use typed_builder::TypedBuilder;
+
+use crate::client::{Error, HelixClient};
+use crate::tokens::TokenSource;
+use crate::transport::Transport;
+
+/// How long to collect keys before sending a request.
+pub const DEFAULT_WINDOW: Duration = Duration::from_millis(10);
+/// How many results to keep in the cache.
+pub const DEFAULT_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(1000) {
+    Some(size) => size,
+    None => unreachable!(),
+};
+
+type BatchResult<R, E> =
+    Result<HashMap<<R as BulkLookup>::Key, <R as BulkLookup>::Item>, Arc<Error<E>>>;
+
+/// The keys loaded within one window, and the result of looking them up.
+struct Batch<R, E>
+where
+    R: BulkLookup,
+{
+    keys: Mutex<Vec<R::Key>>,
+    result: OnceCell<BatchResult<R, E>>,
+}
+
+/// A cached result, and when it expires.
+struct Cached<T> {
+    item: T,
+    expires: Option<Instant>,
+}
+
+struct State<R, E>
+where
+    R: BulkLookup,
+{
+    /// The batch that keys are added to, until its window closes.
+    open: Option<Arc<Batch<R, E>>>,
+    cache: LruCache<R::Key, Cached<R::Item>>,
+}
+
+/// Coalesces concurrent lookups of the endpoint `R` into bulk requests.
+#[derive(TypedBuilder)]
+pub struct Loader<T, A, R>
+where
+    T: Transport,
+    R: BulkLookup,
+{
+    client: Arc<HelixClient<T, A>>,
+    /// The request to copy for each batch, with only the keys replaced.
+    /// Usually the default, without keys.
+    request: R,
+    #[builder(default = DEFAULT_WINDOW)]
+    window: Duration,
+    #[builder(default = DEFAULT_CACHE_SIZE)]
+    cache_size: NonZeroUsize,
+    /// How long a result is kept in the cache. Without this, results are
+    /// only evicted when the cache is full, or by [`Loader::invalidate`].
+    #[builder(default, setter(strip_option))]
+    ttl: Option<Duration>,
+    #[builder(setter(skip), default = Mutex::new(State {
+        open: None,
+        cache: LruCache::new(cache_size),
+    }))]
+    state: Mutex<State<R, T::Error>>,
+}
+
+impl<T, A, R> Loader<T, A, R>
+where
+    T: Transport,
+    A: TokenSource,
+    R: BulkLookup,
+    R::Response: IntoIterator<Item = R::Item>,
+    R::Item: Clone,
+{
+    pub fn client(&self) -> &Arc<HelixClient<T, A>> {
+        &self.client
+    }
+
+    /// The most results that the cache holds.
+    pub fn cache_size(&self) -> NonZeroUsize {
+        self.cache_size
+    }
+
+    /// How long a result is kept in the cache, if limited.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Look up `key`, together with every other key loaded within the same window.
+    /// Returns `None` if it was not found.
+    ///
+    /// If the request fails, every caller waiting for it gets the same error.
+    pub async fn load(&self, key: R::Key) -> Result<Option<R::Item>, Arc<Error<T::Error>>> {
+        let batch = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(cached) = state.cache.get(&key) {
+                if cached
+                    .expires
+                    .is_none_or(|expires| expires > Instant::now())
+                {
+                    return Ok(Some(cached.item.clone()));
+                }
+                state.cache.pop(&key);
+            }
+            let batch = state
+                .open
+                .get_or_insert_with(|| {
+                    Arc::new(Batch {
+                        keys: Mutex::new(Vec::new()),
+                        result: OnceCell::new(),
+                    })
+                })
+                .clone();
+            let mut keys = batch.keys.lock().unwrap();
+            if !keys.contains(&key) {
+                keys.push(key.clone());
+            }
+            drop(keys);
+            batch
+        };
+
+        match batch.result.get_or_init(|| self.send_batch(&batch)).await {
+            Ok(items) => Ok(items.get(&key).cloned()),
+            Err(error) => Err(error.clone()),
+        }
+    }
+
+    /// Remove `key` from the cache, so that the next load requests it again.
+    pub fn invalidate(&self, key: &R::Key) {
+        self.state.lock().unwrap().cache.pop(key);
+    }
+
+    /// Remove every result from the cache.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().cache.clear();
+    }
+
+    async fn send_batch(&self, batch: &Arc<Batch<R, T::Error>>) -> BatchResult<R, T::Error> {
+        Timer::after(self.window).await;
+        let keys = {
+            let mut state = self.state.lock().unwrap();
+            // Close the window, unless a cancelled caller already did.
+            if state
+                .open
+                .as_ref()
+                .is_some_and(|open| Arc::ptr_eq(open, batch))
+            {
+                state.open = None;
+            }
+            batch.keys.lock().unwrap().clone()
+        };
+
+        tracing::debug!(keys = keys.len(), "loading a batch");
+        let request = self.request.with_keys(&keys);
+        let response = self.client.send_bulk(&request).await.map_err(Arc::new)?;
+
+        let expires = self.ttl.map(|ttl| Instant::now() + ttl);
+        let mut state = self.state.lock().unwrap();
+        for (key, item) in &response.found {
+            let item = item.clone();
+            state.cache.put(key.clone(), Cached { item, expires });
+        }
+        Ok(response.found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_lite::future::{block_on, zip};
+    use kekw_endpoints::channels::GetChannelInformation;
+
+    use super::*;
+    use crate::client::tests::client;
+
+    #[test]
+    fn test_load_coalesced() {
+        let client = client(
+            200,
+            r#"{
+                "data": [{
+                    "broadcaster_id": "141981764",
+                    "broadcaster_login": "twitchdev",
+                    "broadcaster_name": "TwitchDev",
+                    "broadcaster_language": "en",
+                    "game_id": "509670",
+                    "game_name": "Science & Technology",
+                    "title": "TwitchDev Monthly Update",
+                    "delay": 0,
+                    "tags": ["DevsInTheKnow"],
+                    "content_classification_labels": [],
+                    "is_branded_content": false
+                }]
+            }"#,
+        );
+        let loader = Loader::<_, _, GetChannelInformation>::builder()
+            .client(Arc::new(client))
+            .request(GetChannelInformation::default())
+            .build();
+
+        let (first, (second, third)) = block_on(zip(
+            loader.load("141981764".to_owned()),
+            zip(
+                loader.load("141981764".to_owned()),
+                loader.load("12826".to_owned()),
+            ),
+        ));
+        assert_eq!(first.unwrap().unwrap().broadcaster_login, "twitchdev");
+        assert!(second.unwrap().is_some());
+        assert!(third.unwrap().is_none());
+
+        let requests = loader.client().transport().requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0]
+            .url
+            .as_str()
+            .ends_with("?broadcaster_id=141981764&broadcaster_id=12826"));
+        drop(requests);
+
+        assert!(block_on(loader.load("141981764".to_owned()))
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            loader.client().transport().requests.lock().unwrap().len(),
+            1
+        );
+
+        let loader = Loader::<_, _, GetChannelInformation>::builder()
+            .client(loader.client().clone())
+            .request(GetChannelInformation::default())
+            .ttl(Duration::ZERO)
+            .build();
+        for _ in 0..2 {
+            assert!(block_on(loader.load("141981764".to_owned()))
+                .unwrap()
+                .is_some());
+        }
+        assert_eq!(
+            loader.client().transport().requests.lock().unwrap().len(),
+            3
+        );
+    }
+}
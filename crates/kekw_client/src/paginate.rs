@@ -0,0 +1,167 @@
+//! Helix endpoints that return lists respond one page at a time, with a
+//! cursor to request the next page. A [`Paginator`] follows the cursors, and
+//! yields the items of every page as a [`Stream`].
+//!
+//! ```ignore
+//! let mut chatters = client
+//!     .paginate(GetChatters::builder().broadcaster_id("123").moderator_id("123").build())
+//!     .page_size(1000)
+//!     .limit(5000);
+//! while let Some(chatter) = chatters.next().await {
+//!     println!("{}", chatter?.user_login);
+//! }
+//! ```
+//!
+//! The stream ends after a page without a cursor, or with an empty cursor,
+//! or without items. It also ends after an error, which is yielded first. Save
+//! [`Paginator::cursor`] to [`resume`](Paginator::resume) from where it stopped.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use kekw_endpoints::{Endpoint, HelixResponse, Paginated};
+
+use crate::client::{Error, HelixClient};
+use crate::tokens::TokenSource;
+use crate::transport::Transport;
+
+type Item<R> = <<R as Endpoint>::Response as IntoIterator>::Item;
+type Page<R, E> = Result<HelixResponse<<R as Endpoint>::Response>, Error<E>>;
+type PageFuture<'a, R, E> = Pin<Box<dyn Future<Output = Page<R, E>> + 'a>>;
+
+/// A [`Stream`] of the items of every page of a paginated endpoint.
+///
+/// Construct this with [`HelixClient::paginate`].
+pub struct Paginator<'a, T, A, R>
+where
+    T: Transport,
+    R: Paginated,
+    R::Response: IntoIterator,
+{
+    client: &'a HelixClient<T, A>,
+    request: R,
+    /// The cursor of the next page to request.
+    cursor: Option<String>,
+    /// The items of the last page that have not been yielded yet.
+    items: VecDeque<Item<R>>,
+    /// The number of items left to yield, if limited.
+    remaining: Option<usize>,
+    page: Option<PageFuture<'a, R, T::Error>>,
+    done: bool,
+}
+
+impl<'a, T, A, R> Paginator<'a, T, A, R>
+where
+    T: Transport,
+    A: TokenSource,
+    R: Paginated + Clone + 'a,
+    R::Response: IntoIterator,
+{
+    pub(crate) fn new(client: &'a HelixClient<T, A>, request: R) -> Self {
+        Self {
+            client,
+            request,
+            cursor: None,
+            items: VecDeque::new(),
+            remaining: None,
+            page: None,
+            done: false,
+        }
+    }
+
+    /// Request at most `size` items per page, rather than the endpoint's default.
+    pub fn page_size(mut self, size: u32) -> Self {
+        self.request.set_page_size(size);
+        self
+    }
+
+    /// Stop after yielding `limit` items, without requesting further pages.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.remaining = Some(limit);
+        self
+    }
+
+    /// Start from the page after `cursor`, rather than the first page.
+    pub fn resume(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into()).filter(|cursor| !cursor.is_empty());
+        self
+    }
+
+    /// The cursor of the next page, or `None` before the first page and after the last.
+    ///
+    /// Items of the current page that have not been yielded are not included
+    /// when resuming from this cursor, so save it when [`Self::buffered`] is zero.
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    /// The number of items of the current page that have not been yielded yet.
+    pub fn buffered(&self) -> usize {
+        self.items.len()
+    }
+
+    fn request_page(&self) -> PageFuture<'a, R, T::Error> {
+        let client = self.client;
+        let mut request = self.request.clone();
+        request.set_cursor(self.cursor.clone());
+        Box::pin(async move { client.send(&request).await })
+    }
+}
+
+// The pending page is boxed, and nothing else is pinned.
+impl<T, A, R> Unpin for Paginator<'_, T, A, R>
+where
+    T: Transport,
+    R: Paginated,
+    R::Response: IntoIterator,
+{
+}
+
+impl<'a, T, A, R> Stream for Paginator<'a, T, A, R>
+where
+    T: Transport,
+    A: TokenSource,
+    R: Paginated + Clone + 'a,
+    R::Response: IntoIterator,
+{
+    type Item = Result<Item<R>, Error<T::Error>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.remaining == Some(0) {
+                return Poll::Ready(None);
+            }
+            if let Some(item) = self.items.pop_front() {
+                if let Some(remaining) = &mut self.remaining {
+                    *remaining -= 1;
+                }
+                return Poll::Ready(Some(Ok(item)));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            if self.page.is_none() {
+                self.page = Some(self.request_page());
+            }
+            let response = ready!(self.page.as_mut().unwrap().as_mut().poll(cx));
+            self.page = None;
+            match response {
+                Ok(response) => {
+                    self.items.extend(response.data);
+                    tracing::trace!(items = self.items.len(), "received a page");
+                    self.cursor = response.pagination.next_cursor();
+                    // An empty page with a cursor would be requested forever.
+                    self.done = self.cursor.is_none() || self.items.is_empty();
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Helix allows each combination of client ID and token a number of points
+//! per minute, and reports the state of the [bucket][0] in the headers of
+//! every response.
+//!
+//! A [`RateLimits`] store keeps the latest state of each bucket. Before a
+//! request is sent, a point is reserved from its bucket, and if the bucket is
+//! empty, the request waits until it refills.
+//!
+//! [0]: https://dev.twitch.tv/docs/api/guide/#twitch-rate-limits
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use async_io::Timer;
+use kekw_oauth2::types::{AccessToken, ClientId};
+
+use crate::transport::HttpResponse;
+
+pub static HEADER_LIMIT: &str = "Ratelimit-Limit";
+pub static HEADER_REMAINING: &str = "Ratelimit-Remaining";
+pub static HEADER_RESET: &str = "Ratelimit-Reset";
+
+/// How often Helix refills a bucket.
+pub const REFILL_PERIOD: Duration = Duration::from_secs(60);
+
+/// The state of a bucket, as of the last response.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The number of points the bucket holds when it is full.
+    pub limit: u32,
+    /// The number of points left, minus those reserved by requests in flight.
+    pub remaining: u32,
+    /// When the bucket is full again.
+    pub reset: SystemTime,
+}
+
+impl RateLimit {
+    /// Read the `Ratelimit-*` headers, if all of them are present and valid.
+    pub fn from_response(response: &HttpResponse) -> Option<Self> {
+        let reset = response.header(HEADER_RESET)?.parse::<u64>().ok()?;
+        Some(Self {
+            limit: response.header(HEADER_LIMIT)?.parse().ok()?,
+            remaining: response.header(HEADER_REMAINING)?.parse().ok()?,
+            reset: SystemTime::UNIX_EPOCH + Duration::from_secs(reset),
+        })
+    }
+
+    /// How long until the bucket is full again, or zero if it already is.
+    pub fn reset_after(&self) -> Duration {
+        self.reset
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+
+    /// Take a point, or return how long to wait for the bucket to refill.
+    ///
+    /// Once the reset time has passed, the bucket is assumed to be full, and
+    /// to refill again [`REFILL_PERIOD`] later, until a response says otherwise.
+    fn reserve(&mut self, now: SystemTime) -> Option<Duration> {
+        if now >= self.reset {
+            self.remaining = self.limit;
+            self.reset = now + REFILL_PERIOD;
+        }
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            None
+        } else {
+            Some(self.reset.duration_since(now).unwrap_or_default())
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct BucketKey {
+    client_id: ClientId,
+    token: AccessToken,
+}
+
+/// Shared storage for the state of every bucket.
+///
+/// Cloning this creates another handle to the same buckets, so that several
+/// clients with the same credentials share them.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimits {
+    buckets: Arc<Mutex<HashMap<BucketKey, RateLimit>>>,
+}
+
+impl RateLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The state of the bucket for a client ID and token,
+    /// if a response has been received for them.
+    pub fn get(&self, client_id: &ClientId, token: &AccessToken) -> Option<RateLimit> {
+        self.buckets
+            .lock()
+            .unwrap()
+            .get(&BucketKey {
+                client_id: client_id.clone(),
+                token: token.clone(),
+            })
+            .copied()
+    }
+
+    /// The state of every bucket, by client ID.
+    /// Tokens are omitted, so that this can be exported safely.
+    pub fn buckets(&self) -> Vec<(ClientId, RateLimit)> {
+        self.buckets
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, bucket)| (key.client_id.clone(), *bucket))
+            .collect()
+    }
+
+    /// Reserve a point from the bucket, waiting for it to refill if it is empty.
+    /// Returns immediately if the bucket is not known yet.
+    pub(crate) async fn acquire(&self, client_id: &ClientId, token: &AccessToken) {
+        let key = BucketKey {
+            client_id: client_id.clone(),
+            token: token.clone(),
+        };
+        loop {
+            let wait = match self.buckets.lock().unwrap().get_mut(&key) {
+                Some(bucket) => bucket.reserve(SystemTime::now()),
+                None => None,
+            };
+            match wait {
+                Some(wait) => {
+                    tracing::debug!(
+                        ?wait,
+                        "rate limit bucket is empty, waiting for it to refill"
+                    );
+                    Timer::after(wait).await;
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Record the state of the bucket from the headers of a response.
+    ///
+    /// Buckets that have been full for a whole [`REFILL_PERIOD`] are dropped,
+    /// so that those of replaced tokens do not pile up.
+    pub(crate) fn update(
+        &self,
+        client_id: &ClientId,
+        token: &AccessToken,
+        response: &HttpResponse,
+    ) -> Option<RateLimit> {
+        let bucket = RateLimit::from_response(response)?;
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = SystemTime::now();
+        buckets.retain(|_, bucket| bucket.reset + REFILL_PERIOD > now);
+        buckets.insert(
+            BucketKey {
+                client_id: client_id.clone(),
+                token: token.clone(),
+            },
+            bucket,
+        );
+        Some(bucket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_after_reset() {
+        let now = SystemTime::now();
+        let mut bucket = RateLimit {
+            limit: 2,
+            remaining: 0,
+            reset: now,
+        };
+        assert_eq!(bucket.reserve(now), None);
+        assert_eq!(bucket.reserve(now), None);
+        assert_eq!(bucket.reserve(now), Some(REFILL_PERIOD));
+        assert_eq!(bucket.reset, now + REFILL_PERIOD);
+        assert_eq!(bucket.reserve(now + REFILL_PERIOD), None);
+    }
+
+    #[test]
+    fn test_update_evicts_stale_buckets() {
+        let limits = RateLimits::new();
+        let client_id = ClientId::new("client".to_owned());
+        let old = AccessToken::new("old".to_owned());
+        let new = AccessToken::new("new".to_owned());
+        limits.buckets.lock().unwrap().insert(
+            BucketKey {
+                client_id: client_id.clone(),
+                token: old.clone(),
+            },
+            RateLimit {
+                limit: 800,
+                remaining: 800,
+                reset: SystemTime::now() - REFILL_PERIOD,
+            },
+        );
+        let reset = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            + REFILL_PERIOD;
+        let response = HttpResponse {
+            headers: vec![
+                (HEADER_LIMIT.to_owned(), "800".to_owned()),
+                (HEADER_REMAINING.to_owned(), "799".to_owned()),
+                (HEADER_RESET.to_owned(), reset.as_secs().to_string()),
+            ],
+            ..Default::default()
+        };
+        assert!(limits.update(&client_id, &new, &response).is_some());
+        assert_eq!(limits.get(&client_id, &old), None);
+        assert!(limits.get(&client_id, &new).is_some());
+    }
+}
@@ -0,0 +1,126 @@
+//! Helix occasionally fails with `500`, `502`, `503` or `504`, or drops the
+//! connection. A [`RetryPolicy`] decides whether to send a request again,
+//! and how long to wait before each attempt.
+//!
+//! The wait doubles with every attempt, up to a maximum, and a random part
+//! of it is removed so that many clients do not retry in lockstep.
+//!
+//! Only idempotent methods are retried by default. A `POST` or `PATCH` could
+//! have taken effect before the failure, so opt in per endpoint with
+//! [`RetryPolicy::allow`] when sending it twice is known to be safe.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+use kekw_endpoints::{Endpoint, Method};
+use rand::Rng;
+use typed_builder::TypedBuilder;
+
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10);
+pub static DEFAULT_RETRY_STATUSES: &[u16] = &[500, 502, 503, 504];
+
+/// Why the client stopped retrying a request that failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GiveUp {
+    /// Every retry allowed by the policy was used.
+    Exhausted,
+    /// The method is not idempotent, and the endpoint was not allowed.
+    NotIdempotent,
+    /// The policy does not retry network errors.
+    NetworkErrorsDisabled,
+}
+
+impl fmt::Display for GiveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Exhausted => "retries exhausted",
+            Self::NotIdempotent => "method is not idempotent",
+            Self::NetworkErrorsDisabled => "network errors are not retried",
+        })
+    }
+}
+
+/// Decides whether, and when, to send a failed request again.
+#[derive(Clone, Debug, TypedBuilder)]
+pub struct RetryPolicy {
+    /// The number of attempts after the first. Zero disables retries.
+    #[builder(default = DEFAULT_MAX_RETRIES)]
+    max_retries: u32,
+    /// The wait before the first retry.
+    #[builder(default = DEFAULT_INITIAL_BACKOFF)]
+    initial_backoff: Duration,
+    #[builder(default = DEFAULT_MAX_BACKOFF)]
+    max_backoff: Duration,
+    /// Remove a random part, up to half, of each wait.
+    #[builder(default = true)]
+    jitter: bool,
+    /// Response statuses that are considered transient.
+    #[builder(default = DEFAULT_RETRY_STATUSES.to_vec())]
+    statuses: Vec<u16>,
+    /// Retry when the transport fails, such as when the connection drops.
+    #[builder(default = true)]
+    network_errors: bool,
+    /// Endpoints with methods that are not idempotent, which may be retried anyway.
+    #[builder(default, setter(skip))]
+    allowed: HashSet<(Method, &'static str)>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::builder().max_retries(0).build()
+    }
+
+    /// Allow retries of the endpoint `R`, even if its method is not idempotent.
+    pub fn allow<R>(mut self) -> Self
+    where
+        R: Endpoint,
+    {
+        self.allowed.insert((R::METHOD, R::PATH));
+        self
+    }
+
+    /// Whether a response with this status is worth retrying.
+    pub fn is_transient(&self, status: u16) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    /// Decide what to do after the request `R` failed for the `attempt`th time,
+    /// counting from one. Returns the time to wait before the next attempt.
+    pub(crate) fn decide<R>(&self, attempt: u32, network_error: bool) -> Result<Duration, GiveUp>
+    where
+        R: Endpoint,
+    {
+        if network_error && !self.network_errors {
+            Err(GiveUp::NetworkErrorsDisabled)
+        } else if !R::METHOD.is_idempotent() && !self.allowed.contains(&(R::METHOD, R::PATH)) {
+            Err(GiveUp::NotIdempotent)
+        } else if attempt > self.max_retries {
+            Err(GiveUp::Exhausted)
+        } else {
+            Ok(self.backoff(attempt))
+        }
+    }
+
+    /// The wait before the retry after the `attempt`th attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        if self.jitter {
+            backoff.mul_f64(1.0 - 0.5 * rand::thread_rng().gen::<f64>())
+        } else {
+            backoff
+        }
+    }
+}
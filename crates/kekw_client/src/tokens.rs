@@ -0,0 +1,287 @@
+//! Some endpoints require the token of a particular user, such as the
+//! broadcaster or one of their moderators. Others accept an app token, or
+//! either kind. A [`TokenRouter`] holds one app token and the tokens of many
+//! users, and picks one for each request.
+//!
+//! - Endpoints that require an app token get the app token.
+//! - Endpoints that require a user token get the token of the user named by
+//!   [`Endpoint::token_user_id`], or of the default user if none is named.
+//! - Endpoints that accept either get the named user's token if there is one,
+//!   then the app token if no scopes are required, then the default user's.
+//!
+//! A user token is only picked if it was granted every scope of the endpoint.
+//! A single [`TokenProvider`], such as an [`AccessToken`], is used for every
+//! request, and its scopes are left for Helix to check.
+//!
+//! Either can be wrapped in [`Validated`], which refuses tokens that a
+//! [`TokenValidator`] has found to be revoked.
+//!
+//! [`TokenValidator`]: kekw_oauth2::validation::TokenValidator
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use kekw_endpoints::{Endpoint, TokenKind};
+use kekw_oauth2::types::{AccessToken, Scopes};
+use kekw_oauth2::validation::ValidatedTokens;
+
+use crate::auth::TokenProvider;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("`{path}` requires an app token, and there is none")]
+    NoAppToken { path: &'static str },
+    #[error("`{path}` requires a user token, and the request does not name a user")]
+    NoUser { path: &'static str },
+    #[error("`{path}` requires the token of user `{user_id}`, and there is none")]
+    NoUserToken { path: &'static str, user_id: String },
+    #[error("`{path}` requires the scopes `{missing}`, which the token of user `{user_id}` lacks")]
+    MissingScopes {
+        path: &'static str,
+        user_id: String,
+        missing: Scopes,
+    },
+    #[error("the token for `{path}` has been revoked")]
+    Revoked { path: &'static str },
+}
+
+/// Picks the token to send with each request.
+pub trait TokenSource: Send + Sync {
+    type Provider: TokenProvider;
+
+    /// The token for `request`, or why there is no suitable one.
+    fn select<R>(&self, request: &R) -> Result<&Self::Provider, Error>
+    where
+        R: Endpoint;
+}
+
+impl<A> TokenSource for A
+where
+    A: TokenProvider,
+{
+    type Provider = A;
+
+    fn select<R>(&self, _request: &R) -> Result<&Self::Provider, Error>
+    where
+        R: Endpoint,
+    {
+        Ok(self)
+    }
+}
+
+struct UserToken<A> {
+    token: A,
+    scopes: Scopes,
+}
+
+/// One app token, and the tokens of many users, keyed by user ID.
+pub struct TokenRouter<A = AccessToken> {
+    app: Option<A>,
+    users: HashMap<String, UserToken<A>>,
+    default_user: Option<String>,
+}
+
+impl<A> Default for TokenRouter<A> {
+    fn default() -> Self {
+        Self {
+            app: None,
+            users: HashMap::new(),
+            default_user: None,
+        }
+    }
+}
+
+impl<A> TokenRouter<A>
+where
+    A: TokenProvider,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the app token.
+    pub fn set_app(&mut self, token: A) -> Option<A> {
+        self.app.replace(token)
+    }
+
+    /// Add or replace the token of a user, with the scopes it was granted.
+    pub fn insert_user(
+        &mut self,
+        user_id: impl Into<String>,
+        token: A,
+        scopes: Scopes,
+    ) -> Option<A> {
+        self.users
+            .insert(user_id.into(), UserToken { token, scopes })
+            .map(|user| user.token)
+    }
+
+    pub fn remove_user(&mut self, user_id: &str) -> Option<A> {
+        self.users.remove(user_id).map(|user| user.token)
+    }
+
+    /// Use the token of this user for requests that require a user token,
+    /// but do not name a user.
+    pub fn set_default_user(&mut self, user_id: Option<String>) {
+        self.default_user = user_id;
+    }
+
+    /// The token of a user, if it has every scope of `R`.
+    fn user<R>(&self, user_id: &str) -> Result<&A, Error>
+    where
+        R: Endpoint,
+    {
+        let user = self.users.get(user_id).ok_or_else(|| Error::NoUserToken {
+            path: R::PATH,
+            user_id: user_id.to_owned(),
+        })?;
+        let missing = Scopes::from_iter(R::SCOPES.iter().copied()) - user.scopes;
+        if missing.is_empty() {
+            Ok(&user.token)
+        } else {
+            Err(Error::MissingScopes {
+                path: R::PATH,
+                user_id: user_id.to_owned(),
+                missing,
+            })
+        }
+    }
+
+    fn app<R>(&self) -> Result<&A, Error>
+    where
+        R: Endpoint,
+    {
+        self.app.as_ref().ok_or(Error::NoAppToken { path: R::PATH })
+    }
+}
+
+impl<A> TokenSource for TokenRouter<A>
+where
+    A: TokenProvider,
+{
+    type Provider = A;
+
+    fn select<R>(&self, request: &R) -> Result<&Self::Provider, Error>
+    where
+        R: Endpoint,
+    {
+        let user_id = request.token_user_id().or(self.default_user.as_deref());
+        match (R::TOKEN, user_id) {
+            (TokenKind::App, _) => self.app::<R>(),
+            (TokenKind::User, Some(user_id)) => self.user::<R>(user_id),
+            (TokenKind::User, None) => Err(Error::NoUser { path: R::PATH }),
+            (TokenKind::Any, _) => {
+                if let Some(token) = request
+                    .token_user_id()
+                    .and_then(|user_id| self.user::<R>(user_id).ok())
+                {
+                    Ok(token)
+                } else if R::SCOPES.is_empty() && self.app.is_some() {
+                    self.app::<R>()
+                } else if let Some(user_id) = user_id {
+                    self.user::<R>(user_id)
+                } else {
+                    self.app::<R>()
+                }
+            }
+        }
+    }
+}
+
+/// A [`TokenSource`] that refuses the tokens of another once they are revoked
+/// in a [`ValidatedTokens`] store.
+#[derive(Debug)]
+pub struct Validated<S, K> {
+    source: S,
+    tokens: ValidatedTokens<K>,
+}
+
+impl<S, K> Validated<S, K> {
+    pub fn new(source: S, tokens: ValidatedTokens<K>) -> Self {
+        Self { source, tokens }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    pub fn tokens(&self) -> &ValidatedTokens<K> {
+        &self.tokens
+    }
+}
+
+impl<S, K> TokenSource for Validated<S, K>
+where
+    S: TokenSource,
+    K: Clone + Eq + Hash + Send,
+{
+    type Provider = S::Provider;
+
+    fn select<R>(&self, request: &R) -> Result<&Self::Provider, Error>
+    where
+        R: Endpoint,
+    {
+        let provider = self.source.select(request)?;
+        if self.tokens.is_token_revoked(&provider.token()) {
+            Err(Error::Revoked { path: R::PATH })
+        } else {
+            Ok(provider)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kekw_endpoints::chat::GetChatters;
+    use kekw_endpoints::users::GetUsers;
+    use kekw_oauth2::types::Scope;
+
+    use super::*;
+
+    #[test]
+    fn test_select_token() {
+        let mut router = TokenRouter::new();
+        let chatters = GetChatters::builder()
+            .broadcaster_id("141981764")
+            .moderator_id("12826")
+            .build();
+        assert!(matches!(
+            router.select(&chatters),
+            Err(Error::NoUserToken { ref user_id, .. }) if user_id == "12826"
+        ));
+        assert!(matches!(
+            router.select(&GetUsers::default()),
+            Err(Error::NoAppToken { .. })
+        ));
+
+        router.set_app(AccessToken::from("app"));
+        router.insert_user("12826", AccessToken::from("moderator"), Scopes::default());
+        router.insert_user(
+            "141981764",
+            AccessToken::from("broadcaster"),
+            Scope::ModeratorReadChatters.into(),
+        );
+        assert!(matches!(
+            router.select(&chatters),
+            Err(Error::MissingScopes { missing, .. }) if missing == Scope::ModeratorReadChatters.into()
+        ));
+        assert_eq!(
+            router.select(&GetUsers::default()).unwrap().expose_secret(),
+            "app"
+        );
+
+        let chatters = GetChatters::builder()
+            .broadcaster_id("141981764")
+            .moderator_id("141981764")
+            .build();
+        assert_eq!(
+            router.select(&chatters).unwrap().expose_secret(),
+            "broadcaster"
+        );
+    }
+}
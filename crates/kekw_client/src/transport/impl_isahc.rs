@@ -0,0 +1,33 @@
+use isahc::{AsyncReadResponseExt, HttpClient, Request};
+
+use super::{HttpRequest, HttpResponse, Transport};
+
+impl Transport for HttpClient {
+    type Error = isahc::Error;
+
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Self::Error> {
+        let mut builder = Request::builder()
+            .method(request.method.as_str())
+            .uri(request.url.as_str());
+        for (name, value) in &request.headers {
+            builder = builder.header(*name, value);
+        }
+        let request = builder.body(request.body.unwrap_or_default())?;
+
+        let mut response = self.send_async(request).await?;
+        let body = response.bytes().await?;
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+            })
+            .collect();
+
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            headers,
+            body,
+        })
+    }
+}
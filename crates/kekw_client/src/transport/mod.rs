@@ -0,0 +1,75 @@
+use std::fmt;
+use std::future::Future;
+
+use kekw_endpoints::Method;
+use url::Url;
+
+#[cfg(feature = "isahc")]
+mod impl_isahc;
+
+/// A request, ready to be sent by a [`Transport`].
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for HttpRequest {
+    /// The value of the `Authorization` header is redacted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                if name.eq_ignore_ascii_case("authorization") {
+                    (*name, "<redacted>")
+                } else {
+                    (*name, value.as_str())
+                }
+            })
+            .collect::<Vec<_>>();
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.url.as_str())
+            .field("headers", &headers)
+            .field("body", &self.body.as_ref().map(|body| body.len()))
+            .finish()
+    }
+}
+
+/// A response, as received by a [`Transport`].
+#[derive(Clone, Debug, Default)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// The value of the first header named `name`, ignoring ASCII case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Sends an [`HttpRequest`] and receives the complete [`HttpResponse`].
+///
+/// Implement this for the HTTP client of your choice, or for a mock in tests.
+/// Responses with error statuses are not errors at this level.
+pub trait Transport {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> impl Future<Output = Result<HttpResponse, Self::Error>> + Send;
+}
@@ -0,0 +1,34 @@
+use kekw_macros::QueryParams;
+use kekw_types::channels::Channel;
+use typed_builder::TypedBuilder;
+
+use crate::{BulkLookup, Endpoint};
+
+/// [Get Channel Information](https://dev.twitch.tv/docs/api/reference/#get-channel-information).
+///
+/// At most 100 broadcaster IDs may be given.
+#[derive(Clone, Debug, Default, TypedBuilder, Endpoint, QueryParams)]
+#[endpoint(GET, "/helix/channels", response = Vec<Channel>)]
+pub struct GetChannelInformation {
+    #[builder(default)]
+    pub broadcaster_id: Vec<String>,
+}
+
+impl BulkLookup for GetChannelInformation {
+    type Key = String;
+    type Item = Channel;
+
+    fn keys(&self) -> Vec<Self::Key> {
+        self.broadcaster_id.clone()
+    }
+
+    fn with_keys(&self, keys: &[Self::Key]) -> Self {
+        Self {
+            broadcaster_id: keys.to_vec(),
+        }
+    }
+
+    fn item_keys(item: &Self::Item) -> Vec<Self::Key> {
+        vec![item.broadcaster_id.clone()]
+    }
+}
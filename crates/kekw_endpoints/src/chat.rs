@@ -0,0 +1,63 @@
+use kekw_macros::QueryParams;
+use kekw_types::users::Chatter;
+use typed_builder::TypedBuilder;
+
+use crate::Endpoint;
+
+/// [Get Chatters](https://dev.twitch.tv/docs/api/reference/#get-chatters).
+#[derive(Clone, Debug, TypedBuilder, Endpoint, QueryParams)]
+#[endpoint(
+    GET,
+    "/helix/chat/chatters",
+    response = Vec<Chatter>,
+    scopes(ModeratorReadChatters),
+    token = user,
+    paginated,
+)]
+pub struct GetChatters {
+    #[builder(setter(into))]
+    pub broadcaster_id: String,
+    /// The broadcaster, or one of their moderators. Must match the user of the token.
+    #[builder(setter(into))]
+    #[endpoint(token_user)]
+    pub moderator_id: String,
+    /// The page size, at most 1000.
+    #[builder(default, setter(strip_option))]
+    pub first: Option<u32>,
+    #[builder(default, setter(strip_option))]
+    pub after: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use kekw_oauth2::types::Scope;
+
+    use super::*;
+    use crate::{Method, Paginated, TokenKind};
+
+    #[test]
+    fn test_endpoint_derive() {
+        let mut request = GetChatters::builder()
+            .broadcaster_id("123")
+            .moderator_id("456")
+            .build();
+        assert_eq!(GetChatters::METHOD, Method::Get);
+        assert_eq!(GetChatters::PATH, "/helix/chat/chatters");
+        assert_eq!(GetChatters::SCOPES, &[Scope::ModeratorReadChatters]);
+        assert_eq!(GetChatters::TOKEN, TokenKind::User);
+
+        request.set_cursor(Some("eyJiIjpudWxsfQ".to_owned()));
+        request.set_page_size(1000);
+        assert_eq!(
+            request.query_pairs(),
+            vec![
+                ("broadcaster_id", "123".to_owned()),
+                ("moderator_id", "456".to_owned()),
+                ("first", "1000".to_owned()),
+                ("after", "eyJiIjpudWxsfQ".to_owned()),
+            ]
+        );
+        assert!(request.body().unwrap().is_none());
+        assert_eq!(request.token_user_id(), Some("456"));
+    }
+}
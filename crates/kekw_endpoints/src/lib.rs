@@ -1,14 +1,147 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+//! Declarations of the endpoints of the Helix API.
+//!
+//! Each request is a struct that implements [`Endpoint`], usually with the
+//! derive of the same name. The trait describes everything a transport needs
+//! to send the request, and to deserialize the `data` of the response.
+
+// The derives refer to this crate by name.
+extern crate self as kekw_endpoints;
+
+use std::hash::Hash;
+
+pub use kekw_macros::Endpoint;
+use kekw_macros::{DisplayStrings, VariantStrings};
+use kekw_oauth2::types::Scope;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Channels, by broadcaster ID.
+pub mod channels;
+
+/// Chat and the users in it.
+pub mod chat;
+
+/// Live streams, by user ID or login.
+pub mod streams;
+
+/// Users, by ID or login.
+pub mod users;
+
+/// The base URL of every Helix endpoint.
+pub static HELIX_BASE_URL: &str = "https://api.twitch.tv";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, VariantStrings, DisplayStrings)]
+pub enum Method {
+    #[static_str("GET")]
+    Get,
+    #[static_str("POST")]
+    Post,
+    #[static_str("PUT")]
+    Put,
+    #[static_str("PATCH")]
+    Patch,
+    #[static_str("DELETE")]
+    Delete,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl Method {
+    /// Whether sending the same request twice has the same effect as sending it once.
+    pub const fn is_idempotent(&self) -> bool {
+        matches!(self, Self::Get | Self::Put | Self::Delete)
+    }
+}
+
+/// The kind of access token that an endpoint accepts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A user access token, from the authorization code or implicit grant flows.
+    User,
+    /// An app access token, from the client credentials grant flow.
+    App,
+    /// Either kind of token.
+    Any,
+}
+
+/// A request to the Helix API.
+pub trait Endpoint {
+    /// The type of the `data` field of the response.
+    type Response: DeserializeOwned;
+
+    const METHOD: Method;
+    /// The path, relative to [`HELIX_BASE_URL`].
+    const PATH: &'static str;
+    /// The scopes that the token must have been granted.
+    const SCOPES: &'static [Scope];
+    const TOKEN: TokenKind;
+
+    /// The decoded `(key, value)` pairs of the query string.
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    /// The JSON body, if the endpoint has one.
+    fn body(&self) -> serde_json::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// The ID of the user whose token must be used, such as the broadcaster
+    /// or the moderator, if the request names one.
+    fn token_user_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// An endpoint that returns results in pages, each of which
+/// has a cursor to pass to the request for the next page.
+pub trait Paginated: Endpoint {
+    /// Request the page after `cursor`, or the first page if it is `None`.
+    fn set_cursor(&mut self, cursor: Option<String>);
+
+    /// Request at most `size` items per page. Each endpoint has its own maximum.
+    fn set_page_size(&mut self, size: u32);
+}
+
+/// An endpoint that looks up several users, channels or streams by ID or
+/// login, and accepts at most [`Self::MAX_KEYS`] of them per request.
+pub trait BulkLookup: Endpoint + Sized {
+    /// An ID or login to look up.
+    type Key: Clone + Eq + Hash;
+    /// The type of each element of the `data` field of the response.
+    type Item;
+
+    /// The most keys that a single request accepts.
+    const MAX_KEYS: usize = 100;
+
+    /// The keys that this request looks up.
+    fn keys(&self) -> Vec<Self::Key>;
+
+    /// A copy of this request that looks up only `keys`.
+    fn with_keys(&self, keys: &[Self::Key]) -> Self;
+
+    /// The keys that would have found this item.
+    fn item_keys(item: &Self::Item) -> Vec<Self::Key>;
+}
+
+/// The body of every successful response from the Helix API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct HelixResponse<T> {
+    pub data: T,
+    #[serde(default)]
+    pub pagination: Pagination,
+    /// Present on some paginated endpoints.
+    pub total: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Pagination {
+    /// The cursor for the next page, or `None` if this is the last page.
+    pub cursor: Option<String>,
+}
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+impl Pagination {
+    /// The cursor for the next page. Some endpoints end with an empty
+    /// cursor, rather than none, which is treated as the last page too.
+    pub fn next_cursor(self) -> Option<String> {
+        self.cursor.filter(|cursor| !cursor.is_empty())
     }
 }
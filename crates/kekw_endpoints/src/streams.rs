@@ -0,0 +1,71 @@
+use kekw_macros::QueryParams;
+use kekw_types::streams::Stream;
+use typed_builder::TypedBuilder;
+
+use crate::users::UserKey;
+use crate::{BulkLookup, Endpoint};
+
+/// [Get Streams](https://dev.twitch.tv/docs/api/reference/#get-streams).
+///
+/// Without any filters, gets every live stream, by viewer count.
+/// At most 100 user IDs and logins may be given in total.
+/// Users who are not live are not in the response.
+#[derive(Clone, Debug, Default, TypedBuilder, Endpoint, QueryParams)]
+#[endpoint(GET, "/helix/streams", response = Vec<Stream>, paginated)]
+pub struct GetStreams {
+    #[builder(default)]
+    pub user_id: Vec<String>,
+    #[builder(default)]
+    pub user_login: Vec<String>,
+    /// At most 100 category IDs.
+    #[builder(default)]
+    pub game_id: Vec<String>,
+    /// ISO 639-1 codes, or `other`. At most 100.
+    #[builder(default)]
+    pub language: Vec<String>,
+    /// The page size, at most 100.
+    #[builder(default, setter(strip_option))]
+    pub first: Option<u32>,
+    #[builder(default, setter(strip_option))]
+    pub after: Option<String>,
+}
+
+impl BulkLookup for GetStreams {
+    type Key = UserKey;
+    type Item = Stream;
+
+    fn keys(&self) -> Vec<Self::Key> {
+        let ids = self.user_id.iter().cloned().map(UserKey::Id);
+        let logins = self
+            .user_login
+            .iter()
+            .map(|login| UserKey::Login(login.to_lowercase()));
+        ids.chain(logins).collect()
+    }
+
+    /// Keeps the other filters, and asks for a page large enough to hold a
+    /// stream for every key.
+    fn with_keys(&self, keys: &[Self::Key]) -> Self {
+        let mut request = Self {
+            user_id: Vec::new(),
+            user_login: Vec::new(),
+            first: Some(keys.len() as u32),
+            after: None,
+            ..self.clone()
+        };
+        for key in keys {
+            match key {
+                UserKey::Id(id) => request.user_id.push(id.clone()),
+                UserKey::Login(login) => request.user_login.push(login.clone()),
+            }
+        }
+        request
+    }
+
+    fn item_keys(item: &Self::Item) -> Vec<Self::Key> {
+        vec![
+            UserKey::Id(item.user_id.clone()),
+            UserKey::Login(item.user_login.clone()),
+        ]
+    }
+}
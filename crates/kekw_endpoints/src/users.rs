@@ -0,0 +1,58 @@
+use kekw_macros::QueryParams;
+use kekw_types::users::User;
+use typed_builder::TypedBuilder;
+
+use crate::{BulkLookup, Endpoint};
+
+/// [Get Users](https://dev.twitch.tv/docs/api/reference/#get-users).
+///
+/// Without any IDs or logins, gets the user of the access token.
+/// At most 100 IDs and logins may be given in total.
+#[derive(Clone, Debug, Default, TypedBuilder, Endpoint, QueryParams)]
+#[endpoint(GET, "/helix/users", response = Vec<User>)]
+pub struct GetUsers {
+    #[builder(default)]
+    pub id: Vec<String>,
+    #[builder(default)]
+    pub login: Vec<String>,
+}
+
+/// A user to look up with [`GetUsers`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UserKey {
+    Id(String),
+    /// Logins are compared in lowercase, as Twitch does.
+    Login(String),
+}
+
+impl BulkLookup for GetUsers {
+    type Key = UserKey;
+    type Item = User;
+
+    fn keys(&self) -> Vec<Self::Key> {
+        let ids = self.id.iter().cloned().map(UserKey::Id);
+        let logins = self
+            .login
+            .iter()
+            .map(|login| UserKey::Login(login.to_lowercase()));
+        ids.chain(logins).collect()
+    }
+
+    fn with_keys(&self, keys: &[Self::Key]) -> Self {
+        let mut request = Self::default();
+        for key in keys {
+            match key {
+                UserKey::Id(id) => request.id.push(id.clone()),
+                UserKey::Login(login) => request.login.push(login.clone()),
+            }
+        }
+        request
+    }
+
+    fn item_keys(item: &Self::Item) -> Vec<Self::Key> {
+        vec![
+            UserKey::Id(item.id.clone()),
+            UserKey::Login(item.login.clone()),
+        ]
+    }
+}
@@ -1,3 +1,14 @@
+/// Dependencies of the code generated by `kekw_macros`. Not public API.
+///
+/// Derives reach these through `kekw_lib`, so that deriving crates need only
+/// depend on this crate, and not on each of them.
+#[doc(hidden)]
+pub mod __private {
+    pub use percent_encoding;
+    pub use serde_json;
+    pub use url;
+}
+
 pub fn add(left: usize, right: usize) -> usize {
     left + right
 }
@@ -1,6 +1,9 @@
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
-use syn::{Attribute, Field, Fields, FieldsNamed, Ident, Item, ItemEnum, Path, PathSegment, Type};
+use syn::{
+    Attribute, Field, Fields, FieldsNamed, GenericArgument, Generics, Ident, Item, PathArguments,
+    PathSegment, Type, TypePath,
+};
 
 pub(crate) trait AttributesExt {
     fn find_by_ident<I>(&self, ident: &I) -> Option<usize>
@@ -62,6 +65,12 @@ impl AttributesExt for Vec<Attribute> {
 
 pub(crate) trait ItemExt {
     fn ident(&self) -> Option<&Ident>;
+
+    /// Generics of a type definition.
+    fn generics(&self) -> Option<&Generics>;
+
+    /// Attributes of a type definition.
+    fn type_attrs(&self) -> Option<&Vec<Attribute>>;
 }
 
 impl ItemExt for Item {
@@ -79,6 +88,26 @@ impl ItemExt for Item {
             _ => None,
         }
     }
+
+    fn generics(&self) -> Option<&Generics> {
+        match self {
+            Item::Enum(it) => Some(&it.generics),
+            Item::Struct(it) => Some(&it.generics),
+            Item::Type(it) => Some(&it.generics),
+            Item::Union(it) => Some(&it.generics),
+            _ => None,
+        }
+    }
+
+    fn type_attrs(&self) -> Option<&Vec<Attribute>> {
+        match self {
+            Item::Enum(it) => Some(&it.attrs),
+            Item::Struct(it) => Some(&it.attrs),
+            Item::Type(it) => Some(&it.attrs),
+            Item::Union(it) => Some(&it.attrs),
+            _ => None,
+        }
+    }
 }
 
 pub(crate) trait FieldsExt {
@@ -114,3 +143,54 @@ impl FieldsExt for Fields {
         }
     }
 }
+
+pub(crate) trait TypeExt {
+    /// If this is a path to a type named `ident` with a single generic type
+    /// argument, such as `Option<T>`, returns that argument.
+    fn generic_argument_of<I>(&self, ident: &I) -> Option<&Type>
+    where
+        I: ?Sized,
+        Ident: PartialEq<I>;
+
+    /// Whether this is one of the type parameters of `generics`, or a reference
+    /// to one. Foreign traits cannot be implemented for these.
+    fn is_type_param_of(&self, generics: &Generics) -> bool;
+}
+
+impl TypeExt for Type {
+    fn generic_argument_of<I>(&self, ident: &I) -> Option<&Type>
+    where
+        I: ?Sized,
+        Ident: PartialEq<I>,
+    {
+        let Type::Path(TypePath { qself: None, path }) = self else {
+            return None;
+        };
+        let PathSegment {
+            ident: segment_ident,
+            arguments: PathArguments::AngleBracketed(arguments),
+        } = path.segments.last()?
+        else {
+            return None;
+        };
+        if segment_ident != ident || arguments.args.len() != 1 {
+            return None;
+        }
+        match arguments.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    fn is_type_param_of(&self, generics: &Generics) -> bool {
+        match self {
+            Type::Path(TypePath { qself: None, path }) => path
+                .get_ident()
+                .is_some_and(|ident| generics.type_params().any(|param| param.ident == *ident)),
+            Type::Reference(reference) => reference.elem.is_type_param_of(generics),
+            Type::Paren(paren) => paren.elem.is_type_param_of(generics),
+            Type::Group(group) => group.elem.is_type_param_of(generics),
+            _ => false,
+        }
+    }
+}
@@ -1,19 +1,27 @@
 mod ext;
 mod parsers;
+#[cfg(test)]
+mod tests;
 
 use proc_macro::TokenStream as TokenStream1;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
-use syn::parse::{Parse, Parser};
 use syn::spanned::Spanned;
 use syn::token::Mut;
-use syn::{Error, Expr, Field, Ident, Index, Item, ItemEnum, ItemStruct};
+use syn::{
+    parse_quote, Error, Expr, ExprLit, Field, Fields, Generics, Ident, Index, Item, ItemEnum,
+    ItemStruct, Lit, LitStr, Type,
+};
 
 use self::ext::*;
-use self::parsers::{VariantExprs, VariantStrings};
+use self::parsers::{EndpointArgs, VariantAliases, VariantExprs, VariantStrings};
+
+// For the VariantDocs derive.
+static DOC_ATTRIBUTE: &str = "doc";
 
 // For the VariantStrings, DisplayStrings, DebugExprs and VariantFromStr derives.
 static STATIC_STRING_ATTRIBUTE: &str = "static_str";
+static OTHER_VARIANT_META_IDENT: &str = "other";
 
 // For the DisplayStrings derive.
 static DISPLAY_EXPRESSION_ATTRIBUTE: &str = "display";
@@ -23,6 +31,18 @@ static DEBUG_EXPRESSION_ATTRIBUTE: &str = "debug";
 
 // For the VariantFromStr derive.
 static FROM_STRING_ATTRIBUTE: &str = "from_str";
+static CASE_INSENSITIVE_META_IDENT: &str = "case_insensitive";
+
+// For the NewTypeFrom derive.
+static NEW_TYPE_ATTRIBUTE: &str = "new_type";
+static AS_REF_META_IDENT: &str = "as_ref";
+static BORROW_META_IDENT: &str = "borrow";
+static INTO_INNER_META_IDENT: &str = "into_inner";
+static INTO_ITER_META_IDENT: &str = "into_iter";
+
+// For the DeserializeFromStr derive.
+static DESERIALIZE_FROM_STRING_ATTRIBUTE: &str = "deserialize_from_str";
+static SEQUENCE_META_IDENT: &str = "seq";
 
 // For the NewTypeDeref derive.
 static DEREF_FIELD_ATTRIBUTE: &str = "deref";
@@ -30,11 +50,26 @@ static DEREF_FIELD_ATTRIBUTE: &str = "deref";
 // For the QueryParams derive.
 static QUERY_PARAM_ATTRIBUTE: &str = "query_param";
 static SKIP_NAMED_FIELD_META_IDENT: &str = "skip";
+static RENAME_FIELD_META_IDENT: &str = "rename";
+static RAW_VALUE_META_IDENT: &str = "raw";
+static REPEAT_FIELD_META_IDENT: &str = "repeat";
+static REPEATED_FIELD_TYPES: &[&str] = &["Vec", "VecDeque", "HashSet", "BTreeSet"];
 static SKIP_IF_PREDICATE_META_IDENT: &str = "skip_if";
 static PROXY_PREDICATE_META_IDENT: &str = "proxy";
+static FROM_PROXY_PREDICATE_META_IDENT: &str = "from_proxy";
 
-pub(crate) fn proc_macro_impl(tokens: impl FnOnce() -> syn::Result<TokenStream2>) -> TokenStream1 {
-    tokens()
+// For the Endpoint derive.
+static ENDPOINT_ATTRIBUTE: &str = "endpoint";
+static BODY_FIELD_META_IDENT: &str = "body";
+static TOKEN_USER_FIELD_META_IDENT: &str = "token_user";
+static CURSOR_FIELD_IDENT: &str = "after";
+static PAGE_SIZE_FIELD_IDENT: &str = "first";
+
+pub(crate) fn proc_macro_impl(
+    item: TokenStream1,
+    expand: impl FnOnce(TokenStream2) -> syn::Result<TokenStream2>,
+) -> TokenStream1 {
+    expand(item.into())
         .unwrap_or_else(syn::Error::into_compile_error)
         .into()
 }
@@ -42,29 +77,169 @@ pub(crate) fn proc_macro_impl(tokens: impl FnOnce() -> syn::Result<TokenStream2>
 /// Derivers implement `AsRef<str>`.
 ///
 /// Define static strings per variant with the `static_str` attribute.
+/// Every variant must be a unit variant, and have this attribute.
+///
+/// One tuple variant with a single string field, such as `Unknown(String)`,
+/// may be marked with `#[static_str(other)]` instead. It holds strings that
+/// do not match any other variant, so that new values do not cause errors.
+/// It is not part of `ALL`, `COUNT` or `iter`, and `as_str` returns the string
+/// it holds, so `as_str` is not `const` for such enums.
+///
+/// Derivers also get these inherent items, which keep lists of every
+/// variant in sync with the definition:
+///
+/// - `const ALL: &[Self]`, every variant in the order they are declared.
+/// - `const COUNT: usize`, the number of variants.
+/// - `const fn as_str(&self) -> &'static str`, the same string as `as_ref`.
+/// - `fn iter() -> impl Iterator<Item = Self>`, which yields every variant
+///   in the order they are declared, without requiring `Clone`.
 #[proc_macro_derive(VariantStrings, attributes(static_str))]
 pub fn derive_variant_strings(item: TokenStream1) -> TokenStream1 {
-    proc_macro_impl(|| {
-        let ItemEnum {
-            ident,
-            generics,
-            mut variants,
-            ..
-        } = ItemEnum::parse.parse(item)?;
-
-        let map = VariantStrings::from_variants(STATIC_STRING_ATTRIBUTE, &mut variants)?;
-        let (variants, values) = map.as_iters();
-
-        Ok(quote!(
-            impl #generics ::std::convert::AsRef<str> for #ident #generics {
-                fn as_ref(&self) -> &str {
+    proc_macro_impl(item, expand_variant_strings)
+}
+
+fn expand_variant_strings(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let ItemEnum {
+        ident,
+        generics,
+        mut variants,
+        ..
+    } = syn::parse2::<ItemEnum>(item)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let map = VariantStrings::from_variants(STATIC_STRING_ATTRIBUTE, &mut variants)?;
+
+    let mut all = Vec::new();
+    let mut values = Vec::new();
+    for variant in &variants {
+        if map.other.as_ref() == Some(&variant.ident) {
+            continue;
+        }
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new(
+                variant.fields.span(),
+                "can only derive `VariantStrings` for enums with unit variants",
+            ));
+        }
+        let value = map.get(&variant.ident).ok_or_else(|| {
+            Error::new(
+                variant.ident.span(),
+                format!(
+                    "require every variant to be marked with `#[{}(\"...\")]`",
+                    STATIC_STRING_ATTRIBUTE
+                ),
+            )
+        })?;
+        all.push(&variant.ident);
+        values.push(value);
+    }
+    let count = all.len();
+
+    let (as_str, iter_other) = if let Some(other) = &map.other {
+        let doc = format!(" The static string of this variant, or the string held by `{other}`.");
+        (
+            quote!(
+                #[doc = #doc]
+                pub fn as_str(&self) -> &str {
+                    match self {
+                        #(Self::#all => #values,)*
+                        Self::#other(value) => ::std::convert::AsRef::<str>::as_ref(value),
+                    }
+                }
+            ),
+            quote!(Self::#other(_) => ::std::unreachable!(),),
+        )
+    } else {
+        (
+            quote!(
+                /// The static string of this variant.
+                pub const fn as_str(&self) -> &'static str {
                     match self {
-                        #(#ident::#variants => #values,)*
+                        #(Self::#all => #values,)*
                     }
                 }
+            ),
+            quote!(),
+        )
+    };
+
+    Ok(quote!(
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Every variant, in the order they are declared.
+            pub const ALL: &'static [Self] = &[#(Self::#all,)*];
+
+            /// The number of variants.
+            pub const COUNT: usize = #count;
+
+            #as_str
+
+            /// Iterate over every variant, in the order they are declared.
+            pub fn iter() -> impl ::std::iter::Iterator<Item = Self> {
+                Self::ALL.iter().map(|variant| match variant {
+                    #(Self::#all => Self::#all,)*
+                    #iter_other
+                })
             }
-        ))
-    })
+        }
+
+        impl #impl_generics ::std::convert::AsRef<str> for #ident #ty_generics #where_clause {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+    ))
+}
+
+/// Derivers get an inherent `docs` method, returning the doc comment of each
+/// variant as a static string.
+///
+/// Lines are joined with a newline, and the single leading space that
+/// `///` comments produce is removed. Variants without documentation return
+/// an empty string.
+#[proc_macro_derive(VariantDocs)]
+pub fn derive_variant_docs(item: TokenStream1) -> TokenStream1 {
+    proc_macro_impl(item, expand_variant_docs)
+}
+
+fn expand_variant_docs(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let ItemEnum {
+        ident,
+        generics,
+        variants,
+        ..
+    } = syn::parse2::<ItemEnum>(item)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut arms = TokenStream2::new();
+
+    for variant in &variants {
+        let mut lines = Vec::new();
+        for attr in variant.attrs.filter_by_ident(DOC_ATTRIBUTE) {
+            if let Expr::Lit(ExprLit {
+                lit: Lit::Str(line),
+                ..
+            }) = &attr.meta.require_name_value()?.value
+            {
+                let line = line.value();
+                lines.push(line.strip_prefix(' ').unwrap_or(&line).to_owned());
+            }
+        }
+        let variant_ident = &variant.ident;
+        let docs = lines.join("\n");
+        let docs = docs.trim();
+        arms.extend(quote!(#ident::#variant_ident { .. } => #docs,));
+    }
+
+    Ok(quote!(
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns the documentation comment of this variant.
+            pub const fn docs(&self) -> &'static str {
+                match self {
+                    #arms
+                }
+            }
+        }
+    ))
 }
 
 macro_rules! impl_derive_format_strings {
@@ -73,42 +248,56 @@ macro_rules! impl_derive_format_strings {
         derive: $derive:ident,
         trait: $trait:ident,
         fn: $ident:ident,
+        expand: $expand:ident,
         attr: $attr_name:ident
     ) => {
         $(#[$meta])*
         #[proc_macro_derive($derive, attributes(static_str, debug))]
         pub fn $ident(item: TokenStream1) -> TokenStream1 {
-            proc_macro_impl(|| {
-                let ItemEnum {
-                    ident,
-                    generics,
-                    mut variants,
-                    ..
-                } = ItemEnum::parse.parse(item)?;
-
-                let mut str_map = VariantStrings::from_variants(STATIC_STRING_ATTRIBUTE, &mut variants)?;
-                let expr_map = VariantExprs::from_variants($attr_name, &mut variants)?;
-
-                expr_map.keys().for_each(|k| {
-                    if str_map.contains_key(k) {
-                        str_map.remove(k);
-                    }
-                });
+            proc_macro_impl(item, $expand)
+        }
+
+        fn $expand(item: TokenStream2) -> syn::Result<TokenStream2> {
+            let ItemEnum {
+                ident,
+                generics,
+                mut variants,
+                ..
+            } = syn::parse2::<ItemEnum>(item)?;
+            let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-                let (str_variants, str_values) = str_map.as_iters();
-                let (variants, values) = expr_map.as_iters();
+            let mut str_map = VariantStrings::from_variants(STATIC_STRING_ATTRIBUTE, &mut variants)?;
+            let expr_map = VariantExprs::from_variants($attr_name, &mut variants)?;
+
+            expr_map.keys().for_each(|k| {
+                if str_map.contains_key(k) {
+                    str_map.remove(k);
+                }
+            });
 
-                Ok(quote!(
-                    impl #generics ::std::fmt::$trait for #ident #generics {
-                        fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
-                            match self {
-                                #(#ident::#str_variants => f.write_str(#str_values),)*
-                                #(#ident::#variants => ::std::fmt::$trait::fmt(&#values, f),)*
-                            }
+            let (str_variants, str_values) = str_map.as_iters(&variants);
+            let (expr_variants, values) = expr_map.as_iters(&variants);
+            let other = str_map
+                .other
+                .as_ref()
+                .filter(|other| !expr_map.contains_key(*other))
+                .map(|other| quote!(
+                    #ident::#other(value) => {
+                        ::std::fmt::$trait::fmt(::std::convert::AsRef::<str>::as_ref(value), f)
+                    }
+                ));
+
+            Ok(quote!(
+                impl #impl_generics ::std::fmt::$trait for #ident #ty_generics #where_clause {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        match self {
+                            #(#ident::#str_variants => f.write_str(#str_values),)*
+                            #(#ident::#expr_variants => ::std::fmt::$trait::fmt(&#values, f),)*
+                            #other
                         }
                     }
-                ))
-            })
+                }
+            ))
         }
     };
 }
@@ -120,10 +309,12 @@ impl_derive_format_strings!(
     ///
     /// This derive is interoperable with [`derive@VariantStrings`]. When a variant
     /// does not does have the `display` attribute, but *does* have `static_str`,
-    /// that literal will be used instead.
+    /// that literal will be used instead. The `#[static_str(other)]` variant
+    /// displays the string it holds.
     derive: DisplayStrings,
     trait: Display,
     fn: derive_display_strings,
+    expand: expand_display_strings,
     attr: DISPLAY_EXPRESSION_ATTRIBUTE
 );
 
@@ -134,283 +325,1100 @@ impl_derive_format_strings!(
     ///
     /// This derive is interoperable with [`derive@VariantStrings`]. When a variant
     /// does not does have the `debug` attribute, but *does* have `static_str`,
-    /// that literal will be used instead.
+    /// that literal will be used instead. The `#[static_str(other)]` variant
+    /// formats the string it holds with `Debug`, so that it is quoted.
     derive: DebugExprs,
     trait: Debug,
     fn: derive_debug_exprs,
+    expand: expand_debug_exprs,
     attr: DEBUG_EXPRESSION_ATTRIBUTE
 );
 
+/// Whether two strings accepted by `VariantFromStr` are the same. String
+/// literals are compared by value, so that `"a"` and `r"a"` are the same,
+/// and any other expressions by their tokens.
+fn same_alias(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(a), ..
+            }),
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(b), ..
+            }),
+        ) => a.value() == b.value(),
+        _ => quote!(#a).to_string() == quote!(#b).to_string(),
+    }
+}
+
+/// Derivers implement [`std::str::FromStr`].
+///
+/// Each variant is parsed from its `static_str`, and from any aliases given by
+/// `from_str` attributes, such as `#[from_str("new", "legacy")]`. Aliases are
+/// usually string literals, but may be any `&'static str` constant.
+///
+/// Mark the enum itself with `#[from_str(case_insensitive)]` to ignore ASCII case.
+///
+/// Failures are reported by a generated `Parse{Ident}Error`, which has the
+/// input, every expected string, and a suggestion for the closest match.
+/// If a variant is marked with `#[static_str(other)]`, unmatched strings are
+/// parsed into it instead, and the error type is [`std::convert::Infallible`].
 #[proc_macro_derive(VariantFromStr, attributes(static_str, from_str))]
 pub fn derive_variant_from_str(item: TokenStream1) -> TokenStream1 {
-    proc_macro_impl(|| {
-        let ItemEnum {
-            ident,
-            generics,
-            mut variants,
-            ..
-        } = ItemEnum::parse.parse(item)?;
+    proc_macro_impl(item, expand_variant_from_str)
+}
 
-        let mut str_map = VariantStrings::from_variants(STATIC_STRING_ATTRIBUTE, &mut variants)?;
-        let from_map = VariantExprs::from_variants(FROM_STRING_ATTRIBUTE, &mut variants)?;
+fn expand_variant_from_str(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let ItemEnum {
+        attrs,
+        vis,
+        ident,
+        generics,
+        mut variants,
+        ..
+    } = syn::parse2::<ItemEnum>(item)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-        from_map.keys().for_each(|k| {
-            if str_map.contains_key(k) {
-                str_map.remove(k);
+    let mut case_insensitive = false;
+    if let Some(attr) = attrs.get_by_ident(FROM_STRING_ATTRIBUTE) {
+        attr.parse_nested_meta(|meta| {
+            #[allow(clippy::unit_arg)]
+            if meta.path.is_ident(CASE_INSENSITIVE_META_IDENT) {
+                Ok(case_insensitive = true)
+            } else {
+                Err(Error::new(meta.path.span(), "unknown meta"))
             }
-        });
+        })?;
+    }
 
-        let (str_variants, str_values) = str_map.as_iters();
-        let (variants, values) = from_map.as_iters();
+    let str_map = VariantStrings::from_variants(STATIC_STRING_ATTRIBUTE, &mut variants)?;
+    let from_map = VariantAliases::from_variants(FROM_STRING_ATTRIBUTE, &mut variants)?;
+
+    let mut parse_variants = Vec::new();
+    let mut parse_values = Vec::new();
+    for variant in &variants {
+        let mut values = Vec::<Expr>::new();
+        if let Some(value) = str_map.get(&variant.ident) {
+            values.push(parse_quote!(#value));
+        }
+        for alias in from_map.get(&variant.ident).into_iter().flatten() {
+            // An alias may repeat the `static_str`, which was once required.
+            if !values.iter().any(|value| same_alias(value, alias)) {
+                values.push(alias.clone());
+            }
+        }
+        for value in values {
+            parse_variants.push(&variant.ident);
+            parse_values.push(value);
+        }
+    }
 
-        let err_ident = Ident::new(&format!("Parse{ident}Error"), Span::call_site());
+    let compare = if case_insensitive {
+        quote!(s.eq_ignore_ascii_case)
+    } else {
+        quote!(s.eq)
+    };
 
-        Ok(quote!(
-            #[derive(::std::clone::Clone, ::std::fmt::Debug, ::kekw_macros::NewTypeFrom)]
-            pub struct #err_ident(::std::string::String);
+    if let Some(other) = &str_map.other {
+        return Ok(quote!(
+            impl #impl_generics ::std::str::FromStr for #ident #ty_generics #where_clause {
+                type Err = ::std::convert::Infallible;
 
-            impl ::std::fmt::Display for #err_ident {
-                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                    write!(f, "string did not match any variant: {}", self.0)
+                fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                    #(
+                        if #compare(#parse_values) {
+                            return ::std::result::Result::Ok(#ident::#parse_variants);
+                        }
+                    )*
+                    ::std::result::Result::Ok(#ident::#other(::std::convert::From::from(s)))
                 }
             }
+        ));
+    }
 
-            impl ::std::error::Error for #err_ident {}
+    let err_ident = Ident::new(&format!("Parse{ident}Error"), Span::call_site());
 
-            impl #generics ::std::str::FromStr for #ident #generics {
-                type Err = #err_ident;
+    Ok(quote!(
+        #[derive(::std::clone::Clone, ::std::fmt::Debug)]
+        #vis struct #err_ident {
+            input: ::std::string::String,
+        }
 
-                fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
-                    match s {
-                        #(#str_values => ::std::result::Result::Ok(#ident::#str_variants),)*
-                        #(#values => ::std::result::Result::Ok(#ident::#variants),)*
-                        _ => ::std::result::Result::Err(#err_ident(s.to_owned()))
+        impl #err_ident {
+            /// Every string that would have been accepted.
+            pub const EXPECTED: &'static [&'static str] = &[#(#parse_values,)*];
+
+            /// The string that did not match any variant.
+            pub fn input(&self) -> &str {
+                &self.input
+            }
+
+            /// Every string that would have been accepted.
+            pub fn expected(&self) -> &'static [&'static str] {
+                Self::EXPECTED
+            }
+
+            /// The expected string that is closest to the input by edit distance,
+            /// ignoring ASCII case, if it is close enough to be a likely typo.
+            pub fn suggestion(&self) -> ::std::option::Option<&'static str> {
+                fn distance(a: &str, b: &str) -> usize {
+                    let b = b.chars().collect::<::std::vec::Vec<_>>();
+                    let mut row = (0..=b.len()).collect::<::std::vec::Vec<_>>();
+                    for (i, a) in a.chars().enumerate() {
+                        let mut diagonal = row[0];
+                        row[0] = i + 1;
+                        for (j, b) in b.iter().enumerate() {
+                            let substitute = diagonal + usize::from(!a.eq_ignore_ascii_case(b));
+                            diagonal = row[j + 1];
+                            row[j + 1] = substitute.min(row[j] + 1).min(row[j + 1] + 1);
+                        }
                     }
+                    row[b.len()]
+                }
+
+                Self::EXPECTED
+                    .iter()
+                    .map(|expected| (distance(&self.input, expected), *expected))
+                    .filter(|(distance, expected)| *distance <= expected.chars().count() / 3)
+                    .min_by_key(|(distance, _)| *distance)
+                    .map(|(_, expected)| expected)
+            }
+        }
+
+        impl ::std::fmt::Display for #err_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(f, "string did not match any variant: `{}`", self.input)?;
+                if let ::std::option::Option::Some(suggestion) = self.suggestion() {
+                    write!(f, ", did you mean `{}`?", suggestion)
+                } else {
+                    write!(f, ", expected one of: {}", Self::EXPECTED.join(", "))
                 }
             }
-        ))
-    })
+        }
+
+        impl ::std::error::Error for #err_ident {}
+
+        /// Construct the error for an input that did not match any variant.
+        impl ::std::convert::From<::std::string::String> for #err_ident {
+            fn from(input: ::std::string::String) -> Self {
+                Self { input }
+            }
+        }
+
+        impl #impl_generics ::std::str::FromStr for #ident #ty_generics #where_clause {
+            type Err = #err_ident;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                #(
+                    if #compare(#parse_values) {
+                        return ::std::result::Result::Ok(#ident::#parse_variants);
+                    }
+                )*
+                ::std::result::Result::Err(#err_ident {
+                    input: s.to_owned(),
+                })
+            }
+        }
+    ))
 }
 
 #[proc_macro_derive(DerefNewType, attributes(deref))]
 pub fn derive_deref_new_type(item: TokenStream1) -> TokenStream1 {
-    proc_macro_impl(|| {
-        let ItemStruct {
-            ident,
-            generics,
-            fields,
-            ..
-        } = ItemStruct::parse.parse(item)?;
-
-        let (field_index, field_ident, target_ty, mutable) = fields
-            .iter()
-            .enumerate()
-            .find_map(|(i, field)| {
-                field
-                    .attrs
-                    .get_by_ident(DEREF_FIELD_ATTRIBUTE)
-                    .map(|attr| (i, &field.ident, &field.ty, attr.parse_args::<Mut>().is_ok()))
-            })
-            .ok_or_else(|| {
-                Error::new(
-                    fields.span(),
-                    format!(
-                        "require a single field to be marked with `#[{}]`",
-                        DEREF_FIELD_ATTRIBUTE
-                    ),
-                )
-            })?;
+    proc_macro_impl(item, expand_deref_new_type)
+}
 
-        macro_rules! impl_deref_new_type {
-            ($field_name:ident) => {
-                quote!(
-                    impl #generics ::std::ops::Deref for #ident #generics {
-                        type Target = #target_ty;
+fn expand_deref_new_type(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let ItemStruct {
+        ident,
+        generics,
+        fields,
+        ..
+    } = syn::parse2::<ItemStruct>(item)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-                        fn deref(&self) -> &Self::Target {
-                            &self.#$field_name
-                        }
+    let (field_index, field_ident, target_ty, mutable) = fields
+        .iter()
+        .enumerate()
+        .find_map(|(i, field)| {
+            field
+                .attrs
+                .get_by_ident(DEREF_FIELD_ATTRIBUTE)
+                .map(|attr| (i, &field.ident, &field.ty, attr.parse_args::<Mut>().is_ok()))
+        })
+        .ok_or_else(|| {
+            Error::new(
+                fields.span(),
+                format!(
+                    "require a single field to be marked with `#[{}]`",
+                    DEREF_FIELD_ATTRIBUTE
+                ),
+            )
+        })?;
+
+    macro_rules! impl_deref_new_type {
+        ($field_name:ident) => {
+            quote!(
+                impl #impl_generics ::std::ops::Deref for #ident #ty_generics #where_clause {
+                    type Target = #target_ty;
+
+                    fn deref(&self) -> &Self::Target {
+                        &self.#$field_name
                     }
-                )
-            };
-            (mut $field_name:ident) => {
-                quote!(
-                    impl #generics ::std::ops::DerefMut for #ident #generics {
-                        fn deref_mut(&mut self) -> &mut Self::Target {
-                            &mut self.#$field_name
-                        }
+                }
+            )
+        };
+        (mut $field_name:ident) => {
+            quote!(
+                impl #impl_generics ::std::ops::DerefMut for #ident #ty_generics #where_clause {
+                    fn deref_mut(&mut self) -> &mut Self::Target {
+                        &mut self.#$field_name
                     }
-                )
-            };
+                }
+            )
+        };
+    }
+
+    let mut tokens = TokenStream2::new();
+
+    if let Some(field_ident) = field_ident {
+        tokens.extend(impl_deref_new_type!(field_ident));
+        if mutable {
+            tokens.extend(impl_deref_new_type!(mut field_ident));
+        }
+    } else {
+        let field_index = Index::from(field_index);
+        tokens.extend(impl_deref_new_type!(field_index));
+        if mutable {
+            tokens.extend(impl_deref_new_type!(mut field_index));
         }
+    }
 
-        let mut tokens = TokenStream2::new();
+    Ok(tokens)
+}
+
+/// Derivers implement `From<Inner>` for a struct with a single field,
+/// which may be named or unnamed.
+///
+/// Mark the struct with `#[new_type(...)]` to also implement:
+///
+/// - `as_ref`: `AsRef<Inner>`.
+/// - `borrow`: `Borrow<Inner>`.
+/// - `into_inner`: an inherent `fn into_inner(self) -> Inner`, and
+///   `From<Self> for Inner` unless `Inner` is a type parameter, for which
+///   the orphan rule forbids it.
+/// - `into_iter`: `IntoIterator` for `Self` and `&Self`, yielding the items
+///   of the inner value.
+#[proc_macro_derive(NewTypeFrom, attributes(new_type))]
+pub fn derive_new_type_from(item: TokenStream1) -> TokenStream1 {
+    proc_macro_impl(item, expand_new_type_from)
+}
+
+fn expand_new_type_from(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let ItemStruct {
+        attrs,
+        ident,
+        generics,
+        fields,
+        ..
+    } = syn::parse2::<ItemStruct>(item)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-        if let Some(field_ident) = field_ident {
-            tokens.extend(impl_deref_new_type!(field_ident));
-            if mutable {
-                tokens.extend(impl_deref_new_type!(mut field_ident));
+    if fields.len() != 1 {
+        return Err(Error::new(fields.span(), "must have a single field"));
+    }
+    let field = fields.iter().next().unwrap();
+    let inner_ty = &field.ty;
+    let (member, construct) = match &field.ident {
+        Some(field_ident) => (quote!(#field_ident), quote!(Self { #field_ident: other })),
+        None => (quote!(0), quote!(Self(other))),
+    };
+
+    let mut as_ref = false;
+    let mut borrow = false;
+    let mut into_inner = false;
+    let mut into_iter = false;
+    if let Some(attr) = attrs.get_by_ident(NEW_TYPE_ATTRIBUTE) {
+        attr.parse_nested_meta(|meta| {
+            #[allow(clippy::unit_arg)]
+            if meta.path.is_ident(AS_REF_META_IDENT) {
+                Ok(as_ref = true)
+            } else if meta.path.is_ident(BORROW_META_IDENT) {
+                Ok(borrow = true)
+            } else if meta.path.is_ident(INTO_INNER_META_IDENT) {
+                Ok(into_inner = true)
+            } else if meta.path.is_ident(INTO_ITER_META_IDENT) {
+                Ok(into_iter = true)
+            } else {
+                Err(Error::new(meta.path.span(), "unknown meta"))
             }
-        } else {
-            let field_index = Index::from(field_index);
-            tokens.extend(impl_deref_new_type!(field_index));
-            if mutable {
-                tokens.extend(impl_deref_new_type!(mut field_index));
+        })?;
+    }
+
+    let mut tokens = quote!(
+        impl #impl_generics ::std::convert::From<#inner_ty> for #ident #ty_generics #where_clause {
+            fn from(other: #inner_ty) -> Self {
+                #construct
             }
         }
+    );
 
-        Ok(tokens)
-    })
-}
+    if as_ref {
+        tokens.extend(quote!(
+            impl #impl_generics ::std::convert::AsRef<#inner_ty> for #ident #ty_generics #where_clause {
+                fn as_ref(&self) -> &#inner_ty {
+                    &self.#member
+                }
+            }
+        ));
+    }
 
-#[proc_macro_derive(NewTypeFrom)]
-pub fn derive_new_type_from(item: TokenStream1) -> TokenStream1 {
-    proc_macro_impl(|| {
-        let ItemStruct {
-            ident,
-            generics,
-            fields,
-            ..
-        } = ItemStruct::parse.parse(item)?;
-
-        if fields.len() != 1 {
-            Err(Error::new(fields.span(), "must have a single field"))
-        } else {
-            let from_ty = &fields.iter().next().unwrap().ty;
-            Ok(quote!(
-                impl #generics ::std::convert::From<#from_ty> for #ident #generics {
-                    fn from(other: #from_ty) -> Self {
-                        Self(other)
+    if borrow {
+        tokens.extend(quote!(
+            impl #impl_generics ::std::borrow::Borrow<#inner_ty> for #ident #ty_generics #where_clause {
+                fn borrow(&self) -> &#inner_ty {
+                    &self.#member
+                }
+            }
+        ));
+    }
+
+    if into_inner {
+        tokens.extend(quote!(
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// Unwrap the inner value.
+                pub fn into_inner(self) -> #inner_ty {
+                    self.#member
+                }
+            }
+        ));
+        if !inner_ty.is_type_param_of(&generics) {
+            tokens.extend(quote!(
+                impl #impl_generics ::std::convert::From<#ident #ty_generics> for #inner_ty #where_clause {
+                    fn from(other: #ident #ty_generics) -> Self {
+                        other.#member
                     }
                 }
-            ))
+            ));
+        }
+    }
+
+    if into_iter {
+        let mut ref_generics = generics.clone();
+        ref_generics.params.insert(0, parse_quote!('__a));
+        ref_generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(&'__a #inner_ty: ::std::iter::IntoIterator));
+        let (ref_impl_generics, _, ref_where_clause) = ref_generics.split_for_impl();
+
+        tokens.extend(quote!(
+            impl #impl_generics ::std::iter::IntoIterator for #ident #ty_generics #where_clause {
+                type Item = <#inner_ty as ::std::iter::IntoIterator>::Item;
+                type IntoIter = <#inner_ty as ::std::iter::IntoIterator>::IntoIter;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    ::std::iter::IntoIterator::into_iter(self.#member)
+                }
+            }
+
+            impl #ref_impl_generics ::std::iter::IntoIterator for &'__a #ident #ty_generics
+            #ref_where_clause
+            {
+                type Item = <&'__a #inner_ty as ::std::iter::IntoIterator>::Item;
+                type IntoIter = <&'__a #inner_ty as ::std::iter::IntoIterator>::IntoIter;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    ::std::iter::IntoIterator::into_iter(&self.#member)
+                }
+            }
+        ));
+    }
+
+    Ok(tokens)
+}
+
+/// Derivers implement [`serde::Serialize`] as a string, formatted with
+/// [`std::fmt::Display`].
+#[proc_macro_derive(SerializeToStr)]
+pub fn derive_serialize_to_str(item: TokenStream1) -> TokenStream1 {
+    proc_macro_impl(item, expand_serialize_to_str)
+}
+
+fn expand_serialize_to_str(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let item = syn::parse2::<Item>(item)?;
+    let (ident, mut generics) = type_ident_generics(&item)?;
+
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: ::std::fmt::Display));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote!(
+        impl #impl_generics ::serde::ser::Serialize for #ident #ty_generics #where_clause {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::serde::ser::Serializer,
+            {
+                serializer.collect_str(self)
+            }
         }
-    })
+    ))
 }
 
-#[proc_macro_derive(DeserializeFromStr)]
+/// Derivers implement [`serde::Deserialize`] from a string, parsed with
+/// [`std::str::FromStr`].
+///
+/// Add `#[deserialize_from_str(seq = T)]` to also accept a sequence of `T`,
+/// which are collected with [`Extend`] into [`Default::default`].
+#[proc_macro_derive(DeserializeFromStr, attributes(deserialize_from_str))]
 pub fn derive_deserialize_from_str(item: TokenStream1) -> TokenStream1 {
-    proc_macro_impl(|| {
-        let item = Item::parse.parse(item)?;
-        let ident = item.ident().ok_or_else(|| {
-            Error::new(item.span(), "this derive only applies to type definitions")
+    proc_macro_impl(item, expand_deserialize_from_str)
+}
+
+fn expand_deserialize_from_str(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let item = syn::parse2::<Item>(item)?;
+    let (ident, generics) = type_ident_generics(&item)?;
+    let visitor_ident = Ident::new(&format!("{ident}Visitor"), Span::call_site());
+
+    let mut seq_ty = None::<Type>;
+    if let Some(attr) = item
+        .type_attrs()
+        .and_then(|attrs| attrs.get_by_ident(DESERIALIZE_FROM_STRING_ATTRIBUTE))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(SEQUENCE_META_IDENT) {
+                seq_ty = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(Error::new(meta.path.span(), "unknown meta"))
+            }
         })?;
-        let visitor_ident = Ident::new(&format!("{ident}Visitor"), Span::call_site());
+    }
 
-        Ok(quote!(
-            struct #visitor_ident;
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let self_ty = quote!(#ident #ty_generics);
 
-            impl<'de> ::serde::de::Visitor<'de> for #visitor_ident {
-                type Value = #ident;
+    let mut de_generics = generics.clone();
+    de_generics.params.insert(0, parse_quote!('de));
+    let de_predicates = &mut de_generics.make_where_clause().predicates;
+    de_predicates.push(parse_quote!(#self_ty: ::std::str::FromStr));
+    de_predicates.push(parse_quote!(
+        <#self_ty as ::std::str::FromStr>::Err: ::std::fmt::Display
+    ));
+    if let Some(seq_ty) = &seq_ty {
+        de_predicates.push(parse_quote!(
+            #self_ty: ::std::default::Default + ::std::iter::Extend<#seq_ty>
+        ));
+        de_predicates.push(parse_quote!(#seq_ty: ::serde::de::Deserialize<'de>));
+    }
+    let (de_impl_generics, _, de_where_clause) = de_generics.split_for_impl();
+    let (visitor_impl_generics, _, visitor_where_clause) = generics.split_for_impl();
 
-                fn visit_str<E>(self, v: &str) -> ::std::result::Result<Self::Value, E>
+    let (visit_seq, deserialize_fn, expecting) = if let Some(seq_ty) = &seq_ty {
+        (
+            quote!(
+                fn visit_seq<A>(self, mut seq: A) -> ::std::result::Result<Self::Value, A::Error>
                 where
-                    E: ::serde::de::Error,
+                    A: ::serde::de::SeqAccess<'de>,
                 {
-                    v.parse().or_else(|e| ::std::result::Result::Err(E::custom(e)))
+                    let mut value = <#self_ty as ::std::default::Default>::default();
+                    while let ::std::option::Option::Some(elem) =
+                        seq.next_element::<#seq_ty>()?
+                    {
+                        value.extend(::std::iter::once(elem));
+                    }
+                    ::std::result::Result::Ok(value)
                 }
+            ),
+            quote!(deserialize_any),
+            "a string or sequence of strings that can be parsed by `<{} as FromStr>::from_str`",
+        )
+    } else {
+        (
+            quote!(),
+            quote!(deserialize_str),
+            "a string that can be parsed by `<{} as FromStr>::from_str`",
+        )
+    };
 
-                fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                    ::std::write!(
-                        f,
-                        "a string that can be parsed by `<{} as FromStr>::from_str`",
-                        ::std::stringify!(#ident)
-                    )
-                }
+    Ok(quote!(
+        struct #visitor_ident #visitor_impl_generics (
+            ::std::marker::PhantomData<fn() -> #self_ty>,
+        ) #visitor_where_clause;
+
+        impl #de_impl_generics ::serde::de::Visitor<'de> for #visitor_ident #ty_generics
+        #de_where_clause
+        {
+            type Value = #self_ty;
+
+            fn visit_str<E>(self, v: &str) -> ::std::result::Result<Self::Value, E>
+            where
+                E: ::serde::de::Error,
+            {
+                v.parse().or_else(|e| ::std::result::Result::Err(E::custom(e)))
             }
 
-            impl<'de> ::serde::de::Deserialize<'de> for #ident {
-                fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
-                where
-                    D: ::serde::de::Deserializer<'de>,
-                {
-                    deserializer.deserialize_str(#visitor_ident)
-                }
+            #visit_seq
+
+            fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::write!(f, #expecting, ::std::stringify!(#ident))
+            }
+        }
+
+        impl #de_impl_generics ::serde::de::Deserialize<'de> for #self_ty #de_where_clause {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: ::serde::de::Deserializer<'de>,
+            {
+                deserializer.#deserialize_fn(#visitor_ident(::std::marker::PhantomData))
             }
-        ))
-    })
+        }
+    ))
 }
 
+/// The identifier and generics of a type definition.
+fn type_ident_generics(item: &Item) -> syn::Result<(&Ident, Generics)> {
+    item.ident()
+        .zip(item.generics())
+        .map(|(ident, generics)| (ident, generics.clone()))
+        .ok_or_else(|| Error::new(item.span(), "this derive only applies to type definitions"))
+}
+
+/// Derivers implement [`std::fmt::Display`] as a query string, and
+/// [`std::str::FromStr`] to parse one. They also get the inherent methods
+/// `to_pairs`, which returns the decoded `(key, value)` pairs,
+/// and `append_to`, which appends those pairs to a [`url::Url`].
+///
+/// Each named field becomes a `key=value` pair, joined by `&`. The value is
+/// formatted with `{:#}` and percent-encoded, leaving only alphanumeric
+/// characters and `-._~` as they are. Encoding and decoding use the
+/// `percent_encoding` crate, and `append_to` uses the `url` crate. Both are
+/// reached through `kekw_lib`, which the deriving crate must depend on.
+/// Parsing decodes each value, and parses it with `FromStr`.
+/// Unknown keys are ignored. Failures are reported by a generated
+/// `Parse{Ident}Error`, which names the offending key.
+///
+/// A field of type `Option<T>` is omitted when it is `None`, and is `None`
+/// when its key is absent. A field of type `Vec<T>`, `VecDeque<T>`,
+/// `HashSet<T>` or `BTreeSet<T>` becomes one pair per element, with the same
+/// key, and collects every occurrence of its key. For these fields, proxies
+/// apply to each element instead of the whole field.
+///
+/// Fields accept these options in the `query_param` attribute:
+///
+/// - `skip` omits the field entirely. It is parsed as `Default::default()`.
+/// - `rename = "..."` uses another key instead of the name of the field.
+/// - `raw` writes the value without percent-encoding it, for values that are
+///   already encoded. It is still decoded when parsing.
+/// - `repeat` treats any other collection like a `Vec<T>`. It requires that
+///   `&T` implements `IntoIterator`, and that `T` implements `FromIterator`.
+/// - `skip_if = fn(&T) -> bool` omits the field when the predicate holds.
+///   When the key is absent while parsing, the field is `Default::default()`.
+/// - `proxy = fn(&T) -> impl Display` formats the value returned by this function.
+/// - `from_proxy = fn(&str) -> Result<T, impl Display>` is the inverse of `proxy`,
+///   used instead of `FromStr` to parse the decoded value.
+///
+/// `Display` formats every field, including those that hold credentials.
+/// For types that are only parsed, derive [`derive@FromQueryParams`] instead.
+///
+/// [`url::Url`]: https://docs.rs/url/latest/url/struct.Url.html
 #[proc_macro_derive(QueryParams, attributes(query_param))]
 pub fn derive_query_params(item: TokenStream1) -> TokenStream1 {
-    proc_macro_impl(|| {
-        let ItemStruct {
-            ident,
-            generics,
-            fields,
-            ..
-        } = ItemStruct::parse.parse(item)?;
-
-        let fields = fields.named().ok_or_else(|| {
-            Error::new(
-                fields.span(),
-                "can only derive `QueryPairs` for structs which have named fields",
+    proc_macro_impl(item, expand_query_params)
+}
+
+/// Derivers implement only the [`std::str::FromStr`] half of
+/// [`derive@QueryParams`], with the same field options.
+///
+/// Use this for query strings that are received rather than sent, such as
+/// redirects that carry credentials, so that they cannot be formatted by
+/// accident. The `raw` and `proxy` options have no effect.
+#[proc_macro_derive(FromQueryParams, attributes(query_param))]
+pub fn derive_from_query_params(item: TokenStream1) -> TokenStream1 {
+    proc_macro_impl(item, expand_from_query_params)
+}
+
+fn expand_query_params(item: TokenStream2) -> syn::Result<TokenStream2> {
+    expand_query_params_impl(item, true)
+}
+
+fn expand_from_query_params(item: TokenStream2) -> syn::Result<TokenStream2> {
+    expand_query_params_impl(item, false)
+}
+
+/// Expand `QueryParams`, or only the parsing half with `FromQueryParams`.
+fn expand_query_params_impl(item: TokenStream2, to_query: bool) -> syn::Result<TokenStream2> {
+    let ItemStruct {
+        vis,
+        ident,
+        generics,
+        fields,
+        ..
+    } = syn::parse2::<ItemStruct>(item)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields = fields.named().ok_or_else(|| {
+        Error::new(
+            fields.span(),
+            format!(
+                "can only derive `{}` for structs which have named fields",
+                if to_query {
+                    "QueryParams"
+                } else {
+                    "FromQueryParams"
+                }
+            ),
+        )
+    })?;
+
+    let err_ident = Ident::new(&format!("Parse{ident}Error"), Span::call_site());
+    // Reached through `kekw_lib`, so that deriving crates need not depend on them.
+    let percent_encoding = quote!(::kekw_lib::__private::percent_encoding);
+    let url = quote!(::kekw_lib::__private::url);
+
+    let mut visit_body = TokenStream2::new();
+    let mut parse_vars = TokenStream2::new();
+    let mut parse_arms = TokenStream2::new();
+    let mut parse_fields = TokenStream2::new();
+
+    for field in fields {
+        let Field {
+            attrs, ident, ty, ..
+        } = field;
+        let field_ident = ident.as_ref().unwrap();
+
+        let mut skip = false;
+        let mut rename = None;
+        let mut raw = false;
+        let mut repeat = REPEATED_FIELD_TYPES
+            .iter()
+            .any(|name| ty.generic_argument_of(name).is_some());
+        let mut skip_if = None;
+        let mut proxy = None;
+        let mut from_proxy = None;
+
+        if let Some(attr) = attrs.get_by_ident(QUERY_PARAM_ATTRIBUTE) {
+            attr.parse_nested_meta(|meta| {
+                #[allow(clippy::unit_arg)]
+                if meta.path.is_ident(SKIP_NAMED_FIELD_META_IDENT) {
+                    Ok(skip = true)
+                } else if meta.path.is_ident(RENAME_FIELD_META_IDENT) {
+                    Ok(rename = Some(meta.value()?.parse::<LitStr>()?.value()))
+                } else if meta.path.is_ident(RAW_VALUE_META_IDENT) {
+                    Ok(raw = true)
+                } else if meta.path.is_ident(REPEAT_FIELD_META_IDENT) {
+                    Ok(repeat = true)
+                } else if meta.path.is_ident(SKIP_IF_PREDICATE_META_IDENT) {
+                    Ok(skip_if = Some(meta.value()?.parse::<Expr>()?))
+                } else if meta.path.is_ident(PROXY_PREDICATE_META_IDENT) {
+                    Ok(proxy = Some(meta.value()?.parse::<Expr>()?))
+                } else if meta.path.is_ident(FROM_PROXY_PREDICATE_META_IDENT) {
+                    Ok(from_proxy = Some(meta.value()?.parse::<Expr>()?))
+                } else {
+                    Err(Error::new(meta.path.span(), "unknown meta"))
+                }
+            })?;
+        };
+
+        if skip {
+            parse_fields.extend(quote!(
+                #field_ident: ::std::default::Default::default(),
+            ));
+            continue;
+        }
+
+        let key = rename.unwrap_or_else(|| field_ident.to_string());
+        let var_ident = Ident::new(&format!("__{field_ident}"), field_ident.span());
+        let option_ty = ty.generic_argument_of("Option");
+
+        // The type of each value, which is parsed and formatted individually.
+        let value_ty = if repeat {
+            quote!(<#ty as ::std::iter::IntoIterator>::Item)
+        } else if let Some(inner_ty) = option_ty {
+            quote!(#inner_ty)
+        } else {
+            quote!(#ty)
+        };
+
+        let parse_expr = if let Some(from_proxy) = from_proxy {
+            quote!((#from_proxy)(&value))
+        } else {
+            quote!(<#value_ty as ::std::str::FromStr>::from_str(&value))
+        };
+        let parse_expr = quote!(#parse_expr.map_err(|e| {
+            #err_ident::Invalid(#key, ::std::string::ToString::to_string(&e))
+        })?);
+
+        let (var_ty, assign, field_value) = if repeat {
+            (
+                quote!(::std::vec::Vec<#value_ty>),
+                quote!(#var_ident.push(#parse_expr)),
+                quote!(#var_ident.into_iter().collect()),
             )
-        })?;
+        } else if option_ty.is_some() {
+            (
+                quote!(::std::option::Option<#value_ty>),
+                quote!(#var_ident = ::std::option::Option::Some(#parse_expr)),
+                quote!(#var_ident),
+            )
+        } else if skip_if.is_some() {
+            (
+                quote!(::std::option::Option<#value_ty>),
+                quote!(#var_ident = ::std::option::Option::Some(#parse_expr)),
+                quote!(#var_ident.unwrap_or_default()),
+            )
+        } else {
+            (
+                quote!(::std::option::Option<#value_ty>),
+                quote!(#var_ident = ::std::option::Option::Some(#parse_expr)),
+                quote!(#var_ident.ok_or(#err_ident::Missing(#key))?),
+            )
+        };
+
+        parse_vars.extend(quote!(
+            let mut #var_ident: #var_ty = ::std::default::Default::default();
+        ));
+        parse_arms.extend(quote!(
+            #key => {
+                let value = value.replace('+', " ");
+                let value = #percent_encoding::percent_decode_str(&value)
+                    .decode_utf8()
+                    .map_err(|_| #err_ident::Decode(#key))?;
+                #assign;
+            }
+        ));
+        parse_fields.extend(quote!(#field_ident: #field_value,));
+
+        let visit_value = |value: TokenStream2| {
+            if let Some(proxy) = &proxy {
+                quote!(visit(#key, #raw, &(#proxy)(#value))?;)
+            } else {
+                quote!(visit(#key, #raw, #value)?;)
+            }
+        };
+
+        let tokens = if repeat {
+            let visit_value = visit_value(quote!(value));
+            quote!(
+                for value in &self.#field_ident {
+                    #visit_value
+                }
+            )
+        } else if option_ty.is_some() {
+            let visit_value = visit_value(quote!(value));
+            quote!(
+                if let ::std::option::Option::Some(value) = &self.#field_ident {
+                    #visit_value
+                }
+            )
+        } else {
+            visit_value(quote!(&self.#field_ident))
+        };
+
+        visit_body.extend(if let Some(skip_if) = skip_if {
+            quote!(
+                if !(#skip_if)(&self.#field_ident) {
+                    #tokens
+                }
+            )
+        } else {
+            tokens
+        });
+    }
 
-        let mut fmt_body = TokenStream2::new();
-
-        for (i, field) in fields.into_iter().enumerate() {
-            let Field { attrs, ident, .. } = field;
-            let field_ident = ident.as_ref().unwrap();
-
-            let mut skip = false;
-            let mut skip_if = None;
-            let mut proxy = None;
-
-            if let Some(attr) = attrs.get_by_ident(QUERY_PARAM_ATTRIBUTE) {
-                attr.parse_nested_meta(|meta| {
-                    #[allow(clippy::unit_arg)]
-                    if meta.path.is_ident(SKIP_NAMED_FIELD_META_IDENT) {
-                        Ok(skip = true)
-                    } else if meta.path.is_ident(SKIP_IF_PREDICATE_META_IDENT) {
-                        Ok(skip_if = Some(meta.value()?.parse::<Expr>()?))
-                    } else if meta.path.is_ident(PROXY_PREDICATE_META_IDENT) {
-                        Ok(proxy = Some(meta.value()?.parse::<Expr>()?))
+    let to_query = to_query.then(|| quote!(
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Call `visit` with the key of every pair that is not skipped,
+            /// whether the value is raw, and the value to format.
+            fn __visit_query_pairs<E>(
+                &self,
+                mut visit: impl ::std::ops::FnMut(
+                    &'static str,
+                    bool,
+                    &dyn ::std::fmt::Display,
+                ) -> ::std::result::Result<(), E>,
+            ) -> ::std::result::Result<(), E> {
+                #visit_body
+                ::std::result::Result::Ok(())
+            }
+
+            /// The `(key, value)` pairs of the query string, in order.
+            /// Values are not percent-encoded, and raw values are decoded.
+            pub fn to_pairs(&self) -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+                let mut pairs = ::std::vec::Vec::new();
+                let _ = self.__visit_query_pairs(|key, raw, value| {
+                    let value = ::std::format!("{:#}", value);
+                    let value = if raw {
+                        #percent_encoding::percent_decode_str(&value)
+                            .decode_utf8_lossy()
+                            .into_owned()
                     } else {
-                        Err(Error::new(meta.path.span(), "unknown meta"))
-                    }
-                })?;
-            };
+                        value
+                    };
+                    pairs.push((key, value));
+                    ::std::result::Result::Ok::<(), ::std::convert::Infallible>(())
+                });
+                pairs
+            }
 
-            if skip {
-                continue;
+            /// Append the pairs of the query string to the query of `url`.
+            pub fn append_to(&self, url: &mut #url::Url) {
+                url.query_pairs_mut().extend_pairs(self.to_pairs());
             }
+        }
 
-            let format = if i == 0 {
-                format!("{field_ident}={{:#}}")
-            } else {
-                format!("&{field_ident}={{:#}}")
-            };
+        impl #impl_generics ::std::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                const QUERY_VALUE: &#percent_encoding::AsciiSet = &#percent_encoding::NON_ALPHANUMERIC
+                    .remove(b'-')
+                    .remove(b'.')
+                    .remove(b'_')
+                    .remove(b'~');
+                let mut separator = "";
+                self.__visit_query_pairs(|key, raw, value| {
+                    if raw {
+                        write!(f, "{}{}={:#}", separator, key, value)?;
+                    } else {
+                        write!(
+                            f,
+                            "{}{}={}",
+                            separator,
+                            key,
+                            #percent_encoding::utf8_percent_encode(
+                                &::std::format!("{:#}", value),
+                                QUERY_VALUE,
+                            ),
+                        )?;
+                    }
+                    separator = "&";
+                    ::std::result::Result::Ok(())
+                })
+            }
+        }
+    ));
 
-            let value_expr = if let Some(proxy) = proxy {
-                quote!((#proxy)(&self.#field_ident))
-            } else {
-                quote!(&self.#field_ident)
-            };
+    Ok(quote!(
+        #to_query
 
-            let tokens = if let Some(skip_if) = skip_if {
-                quote!(
-                    if !(#skip_if)(&self.#field_ident) {
-                        write!(f, #format, #value_expr)?;
+        #[derive(::std::clone::Clone, ::std::fmt::Debug)]
+        #vis enum #err_ident {
+            /// A required key was not present.
+            Missing(&'static str),
+            /// The value of a key was not valid UTF-8 after percent-decoding.
+            Decode(&'static str),
+            /// The value of a key could not be parsed.
+            Invalid(&'static str, ::std::string::String),
+        }
+
+        impl ::std::fmt::Display for #err_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match self {
+                    Self::Missing(key) => write!(f, "missing query parameter `{}`", key),
+                    Self::Decode(key) => write!(
+                        f,
+                        "query parameter `{}` is not valid UTF-8 after percent-decoding",
+                        key
+                    ),
+                    Self::Invalid(key, e) => {
+                        write!(f, "invalid value for query parameter `{}`: {}", key, e)
                     }
-                )
-            } else {
-                quote!(
-                    write!(f, #format, #value_expr)?;
-                )
-            };
+                }
+            }
+        }
 
-            fmt_body.extend(tokens);
+        impl ::std::error::Error for #err_ident {}
+
+        impl #impl_generics ::std::str::FromStr for #ident #ty_generics #where_clause {
+            type Err = #err_ident;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                #parse_vars
+                for pair in s.trim_start_matches('?').split('&') {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    match key {
+                        #parse_arms
+                        _ => {}
+                    }
+                }
+                ::std::result::Result::Ok(Self { #parse_fields })
+            }
         }
+    ))
+}
 
-        Ok(quote!(
-            impl #generics ::std::fmt::Display for #ident #generics {
-                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
-                    #fmt_body
+/// Derivers implement `kekw_endpoints::Endpoint`, describing a request to
+/// the Helix API that any transport can send.
+///
+/// ```ignore
+/// #[derive(Endpoint, QueryParams)]
+/// #[endpoint(GET, "/helix/chat/chatters", response = Vec<Chatter>,
+///            scopes(ModeratorReadChatters), token = user, paginated)]
+/// pub struct GetChatters { ... }
+/// ```
+///
+/// The method is one of `GET`, `POST`, `PUT`, `PATCH` or `DELETE`. Scopes are
+/// variants of `kekw_oauth2::types::Scope`. The token is `user`, `app` or `any`,
+/// the default. Paginated endpoints also implement `kekw_endpoints::Paginated`,
+/// and must have an `after` field of type `Option<String>`, and a `first`
+/// field of type `Option<u32>`.
+///
+/// The query string is taken from the [`derive@QueryParams`] derive, which is
+/// required unless every field is the body. A single field may be marked with
+/// `#[endpoint(body)]` to serialize it as the JSON body, in which case it must
+/// also be marked with `#[query_param(skip)]`. The body is serialized with
+/// `serde_json`, reached through `kekw_lib` like the `QueryParams` dependencies.
+///
+/// The `String` field with the ID of the user whose token must be used, such as
+/// `broadcaster_id` or `moderator_id`, may be marked with `#[endpoint(token_user)]`.
+#[proc_macro_derive(Endpoint, attributes(endpoint))]
+pub fn derive_endpoint(item: TokenStream1) -> TokenStream1 {
+    proc_macro_impl(item, expand_endpoint)
+}
+
+fn expand_endpoint(item: TokenStream2) -> syn::Result<TokenStream2> {
+    let ItemStruct {
+        attrs,
+        ident,
+        generics,
+        fields,
+        ..
+    } = syn::parse2::<ItemStruct>(item)?;
+
+    let EndpointArgs {
+        method,
+        path,
+        response,
+        scopes,
+        token,
+        paginated,
+    } = attrs
+        .get_by_ident(ENDPOINT_ATTRIBUTE)
+        .ok_or_else(|| {
+            Error::new(
+                ident.span(),
+                format!("require the struct to be marked with `#[{ENDPOINT_ATTRIBUTE}(...)]`"),
+            )
+        })?
+        .parse_args::<EndpointArgs>()?;
+
+    let mut body_field = None;
+    let mut token_user_field = None;
+    for field in &fields {
+        if let Some(attr) = field.attrs.get_by_ident(ENDPOINT_ATTRIBUTE) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident(BODY_FIELD_META_IDENT) {
+                    if body_field.replace(field).is_some() {
+                        return Err(Error::new(field.span(), "only one field can be the body"));
+                    }
+                    Ok(())
+                } else if meta.path.is_ident(TOKEN_USER_FIELD_META_IDENT) {
+                    if token_user_field.replace(field).is_some() {
+                        return Err(Error::new(
+                            field.span(),
+                            "only one field can be the token user",
+                        ));
+                    }
                     Ok(())
+                } else {
+                    Err(Error::new(meta.path.span(), "unknown meta"))
                 }
+            })?;
+        }
+    }
+
+    let query_pairs = if fields.len() > usize::from(body_field.is_some()) {
+        quote!(
+            fn query_pairs(&self) -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+                Self::to_pairs(self)
+            }
+        )
+    } else {
+        quote!()
+    };
+
+    let body = if let Some(field) = body_field {
+        let field_ident = field
+            .ident
+            .as_ref()
+            .ok_or_else(|| Error::new(field.span(), "the body must be a named field"))?;
+        quote!(
+            fn body(&self) -> ::kekw_lib::__private::serde_json::Result<::std::option::Option<::std::vec::Vec<u8>>> {
+                ::kekw_lib::__private::serde_json::to_vec(&self.#field_ident).map(::std::option::Option::Some)
+            }
+        )
+    } else {
+        quote!()
+    };
+
+    let token_user_id = if let Some(field) = token_user_field {
+        let field_ident = field
+            .ident
+            .as_ref()
+            .ok_or_else(|| Error::new(field.span(), "the token user must be a named field"))?;
+        quote!(
+            fn token_user_id(&self) -> ::std::option::Option<&str> {
+                ::std::option::Option::Some(&self.#field_ident)
             }
-        ))
-    })
+        )
+    } else {
+        quote!()
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let paginated = if paginated {
+        for (name, field_ident) in [
+            ("cursor", CURSOR_FIELD_IDENT),
+            ("page size", PAGE_SIZE_FIELD_IDENT),
+        ] {
+            let has_field = fields.iter().any(|field| {
+                field
+                    .ident
+                    .as_ref()
+                    .is_some_and(|ident| ident == field_ident)
+            });
+            if !has_field {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("paginated endpoints require a {name} field named `{field_ident}`"),
+                ));
+            }
+        }
+        let cursor = Ident::new(CURSOR_FIELD_IDENT, Span::call_site());
+        let page_size = Ident::new(PAGE_SIZE_FIELD_IDENT, Span::call_site());
+        quote!(
+            impl #impl_generics ::kekw_endpoints::Paginated for #ident #ty_generics #where_clause {
+                fn set_cursor(&mut self, cursor: ::std::option::Option<::std::string::String>) {
+                    self.#cursor = cursor;
+                }
+
+                fn set_page_size(&mut self, size: u32) {
+                    self.#page_size = ::std::option::Option::Some(size);
+                }
+            }
+        )
+    } else {
+        quote!()
+    };
+
+    Ok(quote!(
+        impl #impl_generics ::kekw_endpoints::Endpoint for #ident #ty_generics #where_clause {
+            type Response = #response;
+
+            const METHOD: ::kekw_endpoints::Method = ::kekw_endpoints::Method::#method;
+            const PATH: &'static str = #path;
+            const SCOPES: &'static [::kekw_oauth2::types::Scope] =
+                &[#(::kekw_oauth2::types::Scope::#scopes,)*];
+            const TOKEN: ::kekw_endpoints::TokenKind = ::kekw_endpoints::TokenKind::#token;
+
+            #query_pairs
+
+            #body
+
+            #token_user_id
+        }
+
+        #paginated
+    ))
 }
@@ -1,16 +1,29 @@
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
+use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::token::Comma;
-use syn::{Expr, ExprLit, Ident, Lit, LitStr, Variant};
+use syn::{
+    parenthesized, Error, Expr, ExprLit, ExprPath, Fields, Ident, Lit, LitStr, Token, Type, Variant,
+};
 
 use crate::ext::*;
+use crate::OTHER_VARIANT_META_IDENT;
 
-pub(crate) struct VariantStrings(HashMap<Ident, LitStr>);
+pub(crate) struct VariantStrings {
+    map: HashMap<Ident, LitStr>,
+    /// The tuple variant marked with `other`, which holds unmatched strings.
+    pub other: Option<Ident>,
+}
 
 pub(crate) struct VariantExprs(HashMap<Ident, Expr>);
 
+/// The aliases given by `#[from_str("a", "b")]` on each variant. Each is usually
+/// a string literal, but may be any `&'static str` constant.
+pub(crate) struct VariantAliases(HashMap<Ident, Vec<Expr>>);
+
 impl VariantStrings {
     pub fn from_variants<I>(
         ident: &I,
@@ -21,22 +34,50 @@ impl VariantStrings {
         Ident: PartialEq<I>,
     {
         let mut map = HashMap::new();
+        let mut other = None;
         for variant in variants.iter_mut() {
             if let Some(attr) = variant.attrs.pop_by_ident(ident) {
-                if let Expr::Lit(ExprLit {
-                    attrs: _,
-                    lit: Lit::Str(variant_str),
-                }) = attr.parse_args::<Expr>()?
-                {
-                    map.insert(variant.ident.clone(), variant_str);
+                match attr.parse_args::<Expr>()? {
+                    Expr::Lit(ExprLit {
+                        attrs: _,
+                        lit: Lit::Str(variant_str),
+                    }) => {
+                        map.insert(variant.ident.clone(), variant_str);
+                    }
+                    Expr::Path(ExprPath { path, .. })
+                        if path.is_ident::<str>(OTHER_VARIANT_META_IDENT) =>
+                    {
+                        if !matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1)
+                        {
+                            return Err(Error::new(
+                                variant.span(),
+                                "the `other` variant must have a single unnamed field",
+                            ));
+                        }
+                        if other.replace(variant.ident.clone()).is_some() {
+                            return Err(Error::new(
+                                variant.span(),
+                                "only one variant can be marked as `other`",
+                            ));
+                        }
+                    }
+                    _ => {}
                 }
             }
         }
-        Ok(Self(map))
+        Ok(Self { map, other })
     }
 
-    pub fn as_iters(&self) -> (Vec<&Ident>, Vec<&LitStr>) {
-        (self.keys().collect(), self.values().collect())
+    /// Split into variant identifiers and values, in the order the variants
+    /// are declared, so that the output of a derive is deterministic.
+    pub fn as_iters<'a>(
+        &'a self,
+        variants: &'a Punctuated<Variant, Comma>,
+    ) -> (Vec<&'a Ident>, Vec<&'a LitStr>) {
+        variants
+            .iter()
+            .filter_map(|variant| self.get_key_value(&variant.ident))
+            .unzip()
     }
 }
 
@@ -44,13 +85,13 @@ impl Deref for VariantStrings {
     type Target = HashMap<Ident, LitStr>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.map
     }
 }
 
 impl DerefMut for VariantStrings {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.map
     }
 }
 
@@ -72,8 +113,16 @@ impl VariantExprs {
         Ok(Self(map))
     }
 
-    pub fn as_iters(&self) -> (Vec<&Ident>, Vec<&Expr>) {
-        (self.keys().collect(), self.values().collect())
+    /// Split into variant identifiers and values, in the order the variants
+    /// are declared, so that the output of a derive is deterministic.
+    pub fn as_iters<'a>(
+        &'a self,
+        variants: &'a Punctuated<Variant, Comma>,
+    ) -> (Vec<&'a Ident>, Vec<&'a Expr>) {
+        variants
+            .iter()
+            .filter_map(|variant| self.get_key_value(&variant.ident))
+            .unzip()
     }
 }
 
@@ -90,3 +139,123 @@ impl DerefMut for VariantExprs {
         &mut self.0
     }
 }
+
+impl VariantAliases {
+    /// Collect the expressions from every attribute named `ident` on
+    /// each variant. Each attribute may have several, separated by commas.
+    pub fn from_variants<I>(
+        ident: &I,
+        variants: &mut Punctuated<Variant, Comma>,
+    ) -> syn::Result<Self>
+    where
+        I: ?Sized,
+        Ident: PartialEq<I>,
+    {
+        let mut map = HashMap::new();
+        for variant in variants.iter_mut() {
+            let mut aliases = Vec::new();
+            while let Some(attr) = variant.attrs.pop_by_ident(ident) {
+                aliases.extend(attr.parse_args_with(Punctuated::<Expr, Comma>::parse_terminated)?);
+            }
+            if !aliases.is_empty() {
+                map.insert(variant.ident.clone(), aliases);
+            }
+        }
+        Ok(Self(map))
+    }
+}
+
+impl Deref for VariantAliases {
+    type Target = HashMap<Ident, Vec<Expr>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// The arguments of `#[endpoint(METHOD, "/path", ...)]` on a struct.
+pub(crate) struct EndpointArgs {
+    /// The variant of `kekw_endpoints::Method`.
+    pub method: Ident,
+    pub path: LitStr,
+    pub response: Type,
+    pub scopes: Vec<Ident>,
+    /// The variant of `kekw_endpoints::TokenKind`.
+    pub token: Ident,
+    pub paginated: bool,
+}
+
+impl Parse for EndpointArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let method = input.parse::<Ident>()?;
+        let method = match method.to_string().as_str() {
+            "GET" => Ident::new("Get", method.span()),
+            "POST" => Ident::new("Post", method.span()),
+            "PUT" => Ident::new("Put", method.span()),
+            "PATCH" => Ident::new("Patch", method.span()),
+            "DELETE" => Ident::new("Delete", method.span()),
+            _ => {
+                return Err(Error::new(
+                    method.span(),
+                    "expected one of `GET`, `POST`, `PUT`, `PATCH` or `DELETE`",
+                ))
+            }
+        };
+        input.parse::<Token![,]>()?;
+        let path = input.parse::<LitStr>()?;
+
+        let mut response = None;
+        let mut scopes = Vec::new();
+        let mut token = Ident::new("Any", input.span());
+        let mut paginated = false;
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key = input.parse::<Ident>()?;
+            match key.to_string().as_str() {
+                "response" => {
+                    input.parse::<Token![=]>()?;
+                    response = Some(input.parse::<Type>()?);
+                }
+                "scopes" => {
+                    let content;
+                    parenthesized!(content in input);
+                    scopes.extend(Punctuated::<Ident, Comma>::parse_terminated(&content)?);
+                }
+                "token" => {
+                    input.parse::<Token![=]>()?;
+                    let kind = input.parse::<Ident>()?;
+                    token = match kind.to_string().as_str() {
+                        "user" => Ident::new("User", kind.span()),
+                        "app" => Ident::new("App", kind.span()),
+                        "any" => Ident::new("Any", kind.span()),
+                        _ => {
+                            return Err(Error::new(
+                                kind.span(),
+                                "expected one of `user`, `app` or `any`",
+                            ))
+                        }
+                    };
+                }
+                "paginated" => paginated = true,
+                _ => return Err(Error::new(key.span(), "unknown meta")),
+            }
+        }
+
+        let response = response.ok_or_else(|| {
+            Error::new(path.span(), "require the response type, as `response = T`")
+        })?;
+
+        Ok(Self {
+            method,
+            path,
+            response,
+            scopes,
+            token,
+            paginated,
+        })
+    }
+}
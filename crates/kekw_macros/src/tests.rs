@@ -0,0 +1,238 @@
+//! Snapshots of the code generated by each derive.
+//!
+//! Review changes with `cargo insta review`. Diagnostics are covered by the
+//! `trybuild` suite in `tests/ui`.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use super::*;
+
+fn pretty(expand: fn(TokenStream2) -> syn::Result<TokenStream2>, item: TokenStream2) -> String {
+    let tokens = expand(item).unwrap();
+    prettyplease::unparse(&syn::parse2(tokens).unwrap())
+}
+
+#[test]
+fn test_expand_variant_strings() {
+    insta::assert_snapshot!(pretty(
+        expand_variant_strings,
+        quote!(
+            enum Tier {
+                #[static_str("1000")]
+                One,
+                #[static_str("2000")]
+                Two,
+                #[static_str(other)]
+                Unknown(String),
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_variant_docs() {
+    insta::assert_snapshot!(pretty(
+        expand_variant_docs,
+        quote!(
+            enum Tier {
+                /// The first tier.
+                ///
+                /// Includes ads.
+                One,
+                Two,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_display_strings() {
+    insta::assert_snapshot!(pretty(
+        expand_display_strings,
+        quote!(
+            enum Tier {
+                #[static_str("1000")]
+                One,
+                #[display("tier two")]
+                Two,
+                #[static_str(other)]
+                Unknown(String),
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_debug_exprs() {
+    insta::assert_snapshot!(pretty(
+        expand_debug_exprs,
+        quote!(
+            enum Tier {
+                #[static_str("1000")]
+                One,
+                #[debug(format_args!("{}", 2000))]
+                Two,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_variant_from_str() {
+    insta::assert_snapshot!(pretty(
+        expand_variant_from_str,
+        quote!(
+            #[from_str(case_insensitive)]
+            pub enum Tier {
+                #[static_str("1000")]
+                One,
+                #[static_str("2000")]
+                #[from_str("2000", "two", LEGACY_TWO)]
+                Two,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_variant_from_str_other() {
+    insta::assert_snapshot!(pretty(
+        expand_variant_from_str,
+        quote!(
+            pub enum Tier {
+                #[static_str("1000")]
+                One,
+                #[static_str(other)]
+                Unknown(String),
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_deref_new_type() {
+    insta::assert_snapshot!(pretty(
+        expand_deref_new_type,
+        quote!(
+            struct Login {
+                #[deref(mut)]
+                name: String,
+                id: u64,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_new_type_from() {
+    insta::assert_snapshot!(pretty(
+        expand_new_type_from,
+        quote!(
+            struct UserId(String);
+        )
+    ));
+}
+
+#[test]
+fn test_expand_serialize_to_str() {
+    insta::assert_snapshot!(pretty(
+        expand_serialize_to_str,
+        quote!(
+            struct List<T> {
+                items: Vec<T>,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_deserialize_from_str() {
+    insta::assert_snapshot!(pretty(
+        expand_deserialize_from_str,
+        quote!(
+            #[deserialize_from_str(seq = T)]
+            struct List<T> {
+                items: Vec<T>,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_query_params() {
+    insta::assert_snapshot!(pretty(
+        expand_query_params,
+        quote!(
+            pub struct Query {
+                client_id: String,
+                #[query_param(rename = "type")]
+                kind: Option<String>,
+                #[query_param(raw)]
+                redirect_uri: String,
+                scope: Vec<String>,
+                #[query_param(skip)]
+                extra: u64,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_from_query_params() {
+    insta::assert_snapshot!(pretty(
+        expand_from_query_params,
+        quote!(
+            pub struct Redirect {
+                #[query_param(proxy = AuthCode::expose_secret)]
+                code: AuthCode,
+                state: Option<CsrfState>,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_endpoint() {
+    insta::assert_snapshot!(pretty(
+        expand_endpoint,
+        quote!(
+            #[endpoint(PATCH, "/helix/channels", response = (), scopes(ChannelManageBroadcast), token = user)]
+            pub struct ModifyChannel {
+                #[endpoint(token_user)]
+                broadcaster_id: String,
+                #[endpoint(body)]
+                #[query_param(skip)]
+                body: ChannelUpdate,
+            }
+        )
+    ));
+}
+
+#[test]
+fn test_expand_deref_new_type_generic() {
+    insta::assert_snapshot!(pretty(
+        expand_deref_new_type,
+        quote!(
+            struct Page<T: Clone>(#[deref] Vec<T>)
+            where
+                T: Default;
+        )
+    ));
+}
+
+#[test]
+fn test_expand_new_type_from_generic() {
+    insta::assert_snapshot!(pretty(
+        expand_new_type_from,
+        quote!(
+            #[new_type(as_ref, borrow, into_inner, into_iter)]
+            struct Page<T: Clone>
+            where
+                T: Default,
+            {
+                items: Vec<T>,
+            }
+        )
+    ));
+}
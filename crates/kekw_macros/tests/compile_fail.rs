@@ -0,0 +1,11 @@
+#[test]
+fn test_compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
+
+#[test]
+fn test_compile_pass() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/pass/*.rs");
+}
@@ -0,0 +1,20 @@
+use kekw_macros::NewTypeFrom;
+
+#[derive(NewTypeFrom)]
+#[new_type(as_ref, into_inner)]
+struct Wrapper<T>(T);
+
+#[derive(NewTypeFrom)]
+#[new_type(into_inner)]
+struct Page<T> {
+    items: Vec<T>,
+}
+
+fn main() {
+    let wrapper = Wrapper::from(1u8);
+    assert_eq!(*wrapper.as_ref(), 1);
+    assert_eq!(wrapper.into_inner(), 1);
+
+    let page = Page::from(vec![1, 2]);
+    assert_eq!(Vec::from(page), [1, 2]);
+}
@@ -0,0 +1,38 @@
+use kekw_macros::VariantFromStr;
+
+const LEGACY_TWO: &str = "tier-two";
+
+#[derive(Debug, PartialEq, VariantFromStr)]
+enum Tier {
+    #[static_str("1000")]
+    One,
+    #[static_str("2000")]
+    #[from_str("two", r"two", "2000", LEGACY_TWO)]
+    Two,
+}
+
+#[derive(Debug, PartialEq, VariantFromStr)]
+#[from_str(case_insensitive)]
+enum Color {
+    #[static_str("red")]
+    #[from_str("crimson")]
+    Red,
+}
+
+fn main() {
+    assert_eq!("1000".parse().ok(), Some(Tier::One));
+    assert_eq!("2000".parse().ok(), Some(Tier::Two));
+    assert_eq!("two".parse().ok(), Some(Tier::Two));
+    assert_eq!("tier-two".parse().ok(), Some(Tier::Two));
+
+    let error = "TWO".parse::<Tier>().unwrap_err();
+    assert_eq!(error.input(), "TWO");
+    assert_eq!(error.expected(), ["1000", "2000", "two", "tier-two"]);
+    assert_eq!(error.suggestion(), Some("two"));
+
+    assert_eq!("Red".parse().ok(), Some(Color::Red));
+    assert_eq!("CRIMSON".parse().ok(), Some(Color::Red));
+    let error = "blue".parse::<Color>().unwrap_err();
+    assert_eq!(error.input(), "blue");
+    assert_eq!(error.expected(), ["red", "crimson"]);
+}
@@ -0,0 +1,23 @@
+use kekw_macros::{DisplayStrings, VariantFromStr, VariantStrings};
+
+#[derive(Debug, PartialEq, DisplayStrings, VariantStrings, VariantFromStr)]
+enum Status {
+    #[static_str("active")]
+    Active,
+    #[static_str(other)]
+    Unknown(String),
+}
+
+fn main() {
+    assert_eq!("active".parse(), Ok(Status::Active));
+    assert_eq!("new_value".parse(), Ok(Status::Unknown("new_value".into())));
+
+    let unknown = Status::Unknown("new_value".into());
+    assert_eq!(unknown.as_ref(), "new_value");
+    assert_eq!(unknown.as_str(), "new_value");
+    assert_eq!(unknown.to_string(), "new_value");
+    assert_eq!(Status::Active.to_string(), "active");
+
+    assert_eq!(Status::ALL, [Status::Active]);
+    assert_eq!(Status::COUNT, 1);
+}
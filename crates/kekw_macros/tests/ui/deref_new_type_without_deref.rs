@@ -0,0 +1,8 @@
+use kekw_macros::DerefNewType;
+
+#[derive(DerefNewType)]
+struct Login {
+    name: String,
+}
+
+fn main() {}
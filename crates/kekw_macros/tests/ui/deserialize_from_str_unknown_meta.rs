@@ -0,0 +1,7 @@
+use kekw_macros::DeserializeFromStr;
+
+#[derive(DeserializeFromStr)]
+#[deserialize_from_str(map = String)]
+struct Login(String);
+
+fn main() {}
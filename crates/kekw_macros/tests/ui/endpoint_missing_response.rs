@@ -0,0 +1,7 @@
+use kekw_macros::Endpoint;
+
+#[derive(Endpoint)]
+#[endpoint(GET, "/helix/users", token = app)]
+struct GetUsers;
+
+fn main() {}
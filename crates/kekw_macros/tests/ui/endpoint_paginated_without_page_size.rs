@@ -0,0 +1,9 @@
+use kekw_macros::Endpoint;
+
+#[derive(Endpoint)]
+#[endpoint(GET, "/helix/users", response = (), paginated)]
+struct GetUsers {
+    after: Option<String>,
+}
+
+fn main() {}
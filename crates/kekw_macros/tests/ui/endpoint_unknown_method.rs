@@ -0,0 +1,7 @@
+use kekw_macros::Endpoint;
+
+#[derive(Endpoint)]
+#[endpoint(FETCH, "/helix/users", response = ())]
+struct GetUsers;
+
+fn main() {}
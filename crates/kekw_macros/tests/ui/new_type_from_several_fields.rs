@@ -0,0 +1,6 @@
+use kekw_macros::NewTypeFrom;
+
+#[derive(NewTypeFrom)]
+struct Login(String, u64);
+
+fn main() {}
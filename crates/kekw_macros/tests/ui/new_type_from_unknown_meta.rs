@@ -0,0 +1,7 @@
+use kekw_macros::NewTypeFrom;
+
+#[derive(NewTypeFrom)]
+#[new_type(deref)]
+struct Login(String);
+
+fn main() {}
@@ -0,0 +1,6 @@
+use kekw_macros::QueryParams;
+
+#[derive(QueryParams)]
+struct Query(String);
+
+fn main() {}
@@ -0,0 +1,9 @@
+use kekw_macros::QueryParams;
+
+#[derive(QueryParams)]
+struct Query {
+    #[query_param(rename_all = "camelCase")]
+    client_id: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use kekw_macros::VariantFromStr;
+
+#[derive(VariantFromStr)]
+#[from_str(ignore_case)]
+enum Tier {
+    #[static_str("1000")]
+    One,
+}
+
+fn main() {}
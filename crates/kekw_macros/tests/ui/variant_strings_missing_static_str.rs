@@ -0,0 +1,10 @@
+use kekw_macros::VariantStrings;
+
+#[derive(VariantStrings)]
+enum Tier {
+    #[static_str("1000")]
+    One,
+    Two,
+}
+
+fn main() {}
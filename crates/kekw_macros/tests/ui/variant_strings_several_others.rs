@@ -0,0 +1,11 @@
+use kekw_macros::VariantStrings;
+
+#[derive(VariantStrings)]
+enum Tier {
+    #[static_str(other)]
+    Unknown(String),
+    #[static_str(other)]
+    Other(String),
+}
+
+fn main() {}
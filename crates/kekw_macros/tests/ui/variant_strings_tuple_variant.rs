@@ -0,0 +1,9 @@
+use kekw_macros::VariantStrings;
+
+#[derive(VariantStrings)]
+enum Tier {
+    #[static_str("1000")]
+    One(u8),
+}
+
+fn main() {}
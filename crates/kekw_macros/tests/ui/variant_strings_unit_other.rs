@@ -0,0 +1,9 @@
+use kekw_macros::VariantStrings;
+
+#[derive(VariantStrings)]
+enum Tier {
+    #[static_str(other)]
+    Unknown,
+}
+
+fn main() {}
@@ -13,10 +13,11 @@
 use eyre::{eyre, Context};
 use futures_lite::AsyncReadExt;
 use isahc::HttpClient;
+use kekw_oauth2::credentials::AppCredentials;
 use kekw_oauth2::requests::{AuthCodeQuery, AuthTokenRequestQuery};
 use kekw_oauth2::response::AuthTokenAllowed;
 use kekw_oauth2::server::*;
-use kekw_oauth2::types::{ClientId, ClientSecret, CsrfState, Scopes};
+use kekw_oauth2::types::{CsrfState, Scopes};
 use once_cell::sync::Lazy;
 use url::Url;
 
@@ -30,16 +31,6 @@ static AUTH_LISTEN_IPS: &[&str] = &["127.0.0.1", "0.0.0.0"];
 // This must match your settings on the Twitch Developer Console.
 static REDIRECT_URI: &str = "http://localhost:8833";
 
-static TWITCH_CLIENT_ID: Lazy<ClientId> = Lazy::new(|| {
-    std::env::var("TWITCH_CLIENT_ID")
-        .expect("environment variable `TWITCH_CLIENT_ID` is not set!")
-        .into()
-});
-static TWITCH_CLIENT_SECRET: Lazy<ClientSecret> = Lazy::new(|| {
-    std::env::var("TWITCH_CLIENT_SECRET")
-        .expect("environment variable `TWITCH_CLIENT_SECRET` is not set!")
-        .into()
-});
 /// Change the permissions required by the bot.
 static TWITCH_AUTH_SCOPE: Lazy<Scopes> = Lazy::new(|| {
     use kekw_oauth2::types::Scope::*;
@@ -53,16 +44,21 @@ static TWITCH_AUTH_SCOPE: Lazy<Scopes> = Lazy::new(|| {
 });
 
 fn main() -> eyre::Result<()> {
+    // The redirect URI is fixed, because it must point to the server below.
+    // The Client ID and Client Secret are read from environment variables.
+    let credentials = AppCredentials::loader().redirect_uri(REDIRECT_URI).load()?;
+
     eprintln!(
         r#"Maximum authentication attempts: {AUTH_MAX_TRIES}
 Listening on port: {AUTH_LISTEN_PORT}
 Listening on IP addresses: {}
-Redirect URI: {REDIRECT_URI}
+Redirect URI: {}
 Twitch client ID: {:#5?}
 Authentication scopes requested: {}
 "#,
         AUTH_LISTEN_IPS.join(", "),
-        *TWITCH_CLIENT_ID,
+        credentials.redirect_uri,
+        credentials.client_id,
         *TWITCH_AUTH_SCOPE
     );
 
@@ -75,10 +71,10 @@ Authentication scopes requested: {}
         // Build the initial query according to the table under [Get the user to authorize your app].
         // Some of these are set by default (or are immutable).
         let query = AuthCodeQuery::builder()
-            .client_id(TWITCH_CLIENT_ID.clone())
+            .client_id(credentials.client_id.clone())
             .force_verify() // Disable this if you implement persistence.
-            .redirect_uri(REDIRECT_URI.to_owned())
-            .scope(TWITCH_AUTH_SCOPE.clone())
+            .redirect_uri(credentials.redirect_uri.clone())
+            .scope(*TWITCH_AUTH_SCOPE)
             .state(state.clone()) // randomly generate a state string for CSRF protection.
             .build();
         let url = Url::from(&query);
@@ -100,15 +96,13 @@ Authentication scopes requested: {}
 
         // Use the authorization code to get a token for your session.
         let req_body = AuthTokenRequestQuery::builder()
-            .client_id(TWITCH_CLIENT_ID.clone())
-            .client_secret(TWITCH_CLIENT_SECRET.clone())
+            .client_id(credentials.client_id)
+            .client_secret(credentials.client_secret)
             .code(allow.code)
-            .redirect_uri(REDIRECT_URI.to_owned())
+            .redirect_uri(credentials.redirect_uri)
             .build();
         let req = isahc::Request::from(req_body);
 
-        dbg!(&req);
-
         let res = match client.send_async(req).await {
             Ok(res) if res.status().is_success() => {
                 deserialize_response::<AuthTokenAllowed>(res).await
@@ -119,11 +113,13 @@ Authentication scopes requested: {}
                 body.read_to_end(&mut buf).await?;
                 return Err(eyre!(serde_json::from_slice::<serde_json::Value>(&buf)?));
             }
-            Err(_) => todo!(),
+            Err(err) => return Err(err).wrap_err("failed to send the token request"),
         };
 
-        // Print out the response (including the token).
-        Ok(println!("{:?}", res?.into_body()))
+        // Print out the response. Credentials are redacted by `Debug`.
+        let token = res?.into_body();
+        println!("{token:?}");
+        Ok(())
     })
 }
 
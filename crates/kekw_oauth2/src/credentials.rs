@@ -0,0 +1,292 @@
+//! Values are taken from these sources, in order of precedence:
+//!
+//! 1. Values set explicitly on [`AppCredentialsLoader`].
+//! 2. The environment variables [`ENV_CLIENT_ID`], [`ENV_CLIENT_SECRET`] and
+//!    [`ENV_REDIRECT_URI`]. Empty variables are treated as unset.
+//! 3. A TOML or JSON file, chosen by the file extension, with the keys
+//!    `client_id`, `client_secret` and `redirect_uri`.
+//!
+//! ```no_run
+//! use kekw_oauth2::credentials::AppCredentials;
+//!
+//! let credentials = AppCredentials::loader()
+//!     .redirect_uri("http://localhost:8833")
+//!     .file("twitch.toml")
+//!     .load()?;
+//! # Ok::<(), kekw_oauth2::credentials::Error>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+use serde::Deserialize;
+use typed_builder::TypedBuilder;
+
+use crate::types::{ClientId, ClientSecret};
+
+pub static ENV_CLIENT_ID: &str = "TWITCH_CLIENT_ID";
+pub static ENV_CLIENT_SECRET: &str = "TWITCH_CLIENT_SECRET";
+pub static ENV_REDIRECT_URI: &str = "TWITCH_REDIRECT_URI";
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error(
+        "missing {name}: set it explicitly, with the environment variable `{env_var}`, or as \
+         `{key}` in a credentials file"
+    )]
+    Missing {
+        name: &'static str,
+        env_var: &'static str,
+        key: &'static str,
+    },
+    #[error("environment variable `{0}` is not valid unicode")]
+    NotUnicode(&'static str),
+    #[error("failed to read credentials file `{}`: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("failed to parse credentials file `{}`: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[cfg(feature = "toml")]
+    #[error("failed to parse credentials file `{}`: {source}", path.display())]
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error(
+        "credentials file `{}` is TOML, which requires the `toml` feature of `kekw_oauth2`",
+        .0.display()
+    )]
+    TomlDisabled(PathBuf),
+    #[error(
+        "unsupported credentials file `{}`, expected a `.json` or `.toml` extension",
+        .0.display()
+    )]
+    UnknownFormat(PathBuf),
+}
+
+/// The Client ID, Client Secret and Redirect URI of an application registered
+/// on the Twitch [Developer Console][1].
+///
+/// Construct this with [`AppCredentials::builder`] when every value is known,
+/// or with [`AppCredentials::loader`] to fall back to the environment and files.
+///
+/// The redirect URI is kept as a string, because Twitch requires it to match
+/// the console exactly, and [`url::Url`] would normalize it.
+///
+/// [1]: https://dev.twitch.tv/console
+#[derive(Clone, Debug, TypedBuilder)]
+pub struct AppCredentials {
+    #[builder(setter(into))]
+    pub client_id: ClientId,
+    #[builder(setter(into))]
+    pub client_secret: ClientSecret,
+    #[builder(setter(into))]
+    pub redirect_uri: String,
+}
+
+impl AppCredentials {
+    pub fn loader() -> AppCredentialsLoader {
+        AppCredentialsLoader::default()
+    }
+
+    /// Load every value from the environment variables.
+    pub fn from_env() -> Result<Self, Error> {
+        PartialAppCredentials::from_env()?.complete()
+    }
+
+    /// Load every value from a TOML or JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        PartialAppCredentials::from_file(path.as_ref())?.complete()
+    }
+}
+
+/// Collects [`AppCredentials`] from several sources.
+/// See the [module documentation](self) for the order of precedence.
+#[derive(Debug)]
+pub struct AppCredentialsLoader {
+    explicit: PartialAppCredentials,
+    env: bool,
+    file: Option<PathBuf>,
+}
+
+impl Default for AppCredentialsLoader {
+    fn default() -> Self {
+        Self {
+            explicit: PartialAppCredentials::default(),
+            env: true,
+            file: None,
+        }
+    }
+}
+
+impl AppCredentialsLoader {
+    pub fn client_id(mut self, client_id: impl Into<ClientId>) -> Self {
+        self.explicit.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn client_secret(mut self, client_secret: impl Into<ClientSecret>) -> Self {
+        self.explicit.client_secret = Some(client_secret.into());
+        self
+    }
+
+    pub fn redirect_uri(mut self, redirect_uri: impl Into<String>) -> Self {
+        self.explicit.redirect_uri = Some(redirect_uri.into());
+        self
+    }
+
+    /// Read missing values from a file. It is an error if the file does not exist.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Do not read any values from environment variables.
+    pub fn without_env(mut self) -> Self {
+        self.env = false;
+        self
+    }
+
+    pub fn load(self) -> Result<AppCredentials, Error> {
+        let mut credentials = self.explicit;
+        if self.env {
+            credentials = credentials.or(PartialAppCredentials::from_env()?);
+        }
+        if let Some(path) = &self.file {
+            credentials = credentials.or(PartialAppCredentials::from_file(path)?);
+        }
+        credentials.complete()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PartialAppCredentials {
+    client_id: Option<ClientId>,
+    client_secret: Option<ClientSecret>,
+    redirect_uri: Option<String>,
+}
+
+impl PartialAppCredentials {
+    fn from_env() -> Result<Self, Error> {
+        fn var(name: &'static str) -> Result<Option<String>, Error> {
+            match env::var(name) {
+                Ok(value) if value.is_empty() => Ok(None),
+                Ok(value) => Ok(Some(value)),
+                Err(env::VarError::NotPresent) => Ok(None),
+                Err(env::VarError::NotUnicode(_)) => Err(Error::NotUnicode(name)),
+            }
+        }
+
+        Ok(Self {
+            client_id: var(ENV_CLIENT_ID)?.map(ClientId::from),
+            client_secret: var(ENV_CLIENT_SECRET)?.map(ClientSecret::from),
+            redirect_uri: var(ENV_REDIRECT_URI)?,
+        })
+    }
+
+    fn from_file(path: &Path) -> Result<Self, Error> {
+        let read = || {
+            fs::read_to_string(path).map_err(|source| Error::Io {
+                path: path.to_owned(),
+                source,
+            })
+        };
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&read()?).map_err(|source| Error::Json {
+                path: path.to_owned(),
+                source,
+            }),
+            #[cfg(feature = "toml")]
+            Some("toml") => toml::from_str(&read()?).map_err(|source| Error::Toml {
+                path: path.to_owned(),
+                source,
+            }),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(Error::TomlDisabled(path.to_owned())),
+            _ => Err(Error::UnknownFormat(path.to_owned())),
+        }
+    }
+
+    /// Fill in the values that are missing from `self` with those from `other`.
+    fn or(self, other: Self) -> Self {
+        Self {
+            client_id: self.client_id.or(other.client_id),
+            client_secret: self.client_secret.or(other.client_secret),
+            redirect_uri: self.redirect_uri.or(other.redirect_uri),
+        }
+    }
+
+    fn complete(self) -> Result<AppCredentials, Error> {
+        Ok(AppCredentials {
+            client_id: self.client_id.ok_or(Error::Missing {
+                name: "client ID",
+                env_var: ENV_CLIENT_ID,
+                key: "client_id",
+            })?,
+            client_secret: self.client_secret.ok_or(Error::Missing {
+                name: "client secret",
+                env_var: ENV_CLIENT_SECRET,
+                key: "client_secret",
+            })?,
+            redirect_uri: self.redirect_uri.ok_or(Error::Missing {
+                name: "redirect URI",
+                env_var: ENV_REDIRECT_URI,
+                key: "redirect_uri",
+            })?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_precedence() {
+        // Tests of other processes may run at the same time.
+        let path = env::temp_dir().join(format!(
+            "kekw_oauth2_test_credentials_{}.json",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            r#"{ "client_id": "from_file", "redirect_uri": "http://localhost:8833" }"#,
+        )
+        .unwrap();
+
+        let credentials = AppCredentials::loader()
+            .without_env()
+            .client_id("explicit")
+            .client_secret("secret")
+            .file(&path)
+            .load()
+            .unwrap();
+        assert_eq!(credentials.client_id.as_str(), "explicit");
+        assert_eq!(credentials.client_secret.expose_secret(), "secret");
+        assert_eq!(credentials.redirect_uri, "http://localhost:8833");
+
+        let error = AppCredentials::loader()
+            .without_env()
+            .file(&path)
+            .load()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Missing {
+                key: "client_secret",
+                ..
+            }
+        ));
+
+        fs::remove_file(path).unwrap();
+
+        #[cfg(not(feature = "toml"))]
+        assert!(matches!(
+            AppCredentials::from_file("twitch.toml"),
+            Err(Error::TomlDisabled(_))
+        ));
+    }
+}
@@ -1,3 +1,7 @@
+/// Load the Client ID, Client Secret and Redirect URI of your application
+/// from the environment, a configuration file, or explicit values.
+pub mod credentials;
+
 /// Start here.
 ///
 /// Construct these types using the provided builder, and then either call
@@ -14,3 +18,7 @@ pub mod server;
 
 /// Atomics that appear in [`requests`] or [`response`].
 pub mod types;
+
+/// Validate tokens periodically, and stop using them once they are revoked.
+/// This is agnostic to your HTTP client and async runtime.
+pub mod validation;
@@ -1,10 +1,28 @@
-use http_types::Request;
+use http_types::{Method, Request};
+use url::Url;
 
-/// Encodes [`AuthTokenReqBody`] with `Display`/`ToString`.
+use crate::requests::{AuthTokenRequestQuery, RefreshTokenRequestQuery, ValidateTokenRequest};
+
+/// Encodes [`AuthTokenRequestQuery`] with `Display`/`ToString`.
 /// This is a "form encoded" query string, except it is not actually form encoded.
-/// We bypass `serde_qs` for the encoding, and for now, only utilize it for decoding.
+/// Both encoding and decoding are generated by the `QueryParams` derive.
 impl From<AuthTokenRequestQuery> for Request {
     fn from(query: AuthTokenRequestQuery) -> Request {
-        Request::post(Url::from(query))
+        Request::new(Method::Post, Url::from(query))
+    }
+}
+
+impl From<RefreshTokenRequestQuery> for Request {
+    fn from(query: RefreshTokenRequestQuery) -> Request {
+        Request::new(Method::Post, Url::from(query))
+    }
+}
+
+impl From<ValidateTokenRequest> for Request {
+    fn from(request: ValidateTokenRequest) -> Request {
+        let (name, value) = request.authorization();
+        let mut http_request = Request::new(Method::Get, ValidateTokenRequest::url());
+        http_request.insert_header(name, value);
+        http_request
     }
 }
@@ -1,7 +1,7 @@
 use isahc::Request;
 use url::Url;
 
-use crate::requests::AuthTokenRequestQuery;
+use crate::requests::{AuthTokenRequestQuery, RefreshTokenRequestQuery, ValidateTokenRequest};
 
 impl From<AuthTokenRequestQuery> for Request<()> {
     fn from(query: AuthTokenRequestQuery) -> Request<()> {
@@ -12,6 +12,25 @@ impl From<AuthTokenRequestQuery> for Request<()> {
     }
 }
 
+impl From<RefreshTokenRequestQuery> for Request<()> {
+    fn from(query: RefreshTokenRequestQuery) -> Request<()> {
+        let url = Url::from(query);
+        Request::post(url.as_str())
+            .body(())
+            .expect("failed to set request body")
+    }
+}
+
+impl From<ValidateTokenRequest> for Request<()> {
+    fn from(request: ValidateTokenRequest) -> Request<()> {
+        let (name, value) = request.authorization();
+        Request::get(ValidateTokenRequest::url().as_str())
+            .header(name, value)
+            .body(())
+            .expect("failed to set request body")
+    }
+}
+
 // impl From<AuthTokenRequestBody> for AsyncBody {
 //     fn from(value: AuthTokenRequestBody) -> Self {
 //         AsyncBody::from(dbg!(
@@ -8,29 +8,32 @@
 
 #[cfg(feature = "isahc")]
 #[path = "./impl/impl_isahc.rs"]
-mod r#impl;
+mod impl_isahc;
 
-#[cfg(feature = "http_types")]
+#[cfg(feature = "http-types")]
 #[path = "./impl/impl_http_types.rs"]
-mod r#impl;
+mod impl_http_types;
 
 use kekw_macros::QueryParams;
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 use url::Url;
 
-use crate::types::{AuthCode, ClientId, ClientSecret, CsrfState, Scopes};
+use crate::types::{
+    AccessToken, AuthCode, ClientId, ClientSecret, CsrfState, RefreshToken, Scopes,
+};
 
 static AUTHORIZE_CODE_REQUEST_URL: &str = "https://id.twitch.tv/oauth2/authorize";
 static AUTHORIZE_TOKEN_REQUEST_URL: &str = "https://id.twitch.tv/oauth2/token";
+static VALIDATE_TOKEN_REQUEST_URL: &str = "https://id.twitch.tv/oauth2/validate";
 
-fn unwrap_option<T>(opt: &Option<T>) -> &T {
-    opt.as_ref().unwrap()
-}
-
-fn percent_encode(source: impl ToString) -> String {
-    use percent_encoding::NON_ALPHANUMERIC;
-    percent_encoding::percent_encode(source.to_string().as_ref(), NON_ALPHANUMERIC).collect()
+/// Parse the value of a field that is always set to `expected`.
+fn parse_constant(value: &str, expected: &'static str) -> Result<&'static str, String> {
+    if value == expected {
+        Ok(expected)
+    } else {
+        Err(format!("expected `{expected}`"))
+    }
 }
 
 /// [Authorization code grant flow][0] during the [second][2] step.
@@ -43,9 +46,12 @@ fn percent_encode(source: impl ToString) -> String {
 #[derive(Debug, Serialize, Deserialize, TypedBuilder, QueryParams)]
 pub struct AuthTokenRequestQuery {
     pub client_id: ClientId,
+    #[query_param(proxy = ClientSecret::expose_secret)]
     pub client_secret: ClientSecret,
+    #[query_param(proxy = AuthCode::expose_secret)]
     pub code: AuthCode,
     #[builder(default = "authorization_code", setter(skip))]
+    #[query_param(from_proxy = |value: &str| parse_constant(value, "authorization_code"))]
     grant_type: &'static str,
     pub redirect_uri: String,
 }
@@ -58,6 +64,37 @@ impl From<AuthTokenRequestQuery> for Url {
     }
 }
 
+/// [Refresh an access token][0] that has expired, or that was rejected with
+/// `401 Unauthorized`.
+///
+/// [0]: https://dev.twitch.tv/docs/authentication/refresh-tokens/
+///
+/// Send a `POST` request to the URL this converts into. A successful response
+/// is an [`AuthTokenAllowed`], which may have a new refresh token. A response
+/// with `400 Bad Request` means the refresh token is no longer valid,
+/// and the user must authorize your app again.
+///
+/// [`AuthTokenAllowed`]: crate::response::AuthTokenAllowed
+#[derive(Debug, Serialize, Deserialize, TypedBuilder, QueryParams)]
+pub struct RefreshTokenRequestQuery {
+    pub client_id: ClientId,
+    #[query_param(proxy = ClientSecret::expose_secret)]
+    pub client_secret: ClientSecret,
+    #[builder(default = "refresh_token", setter(skip))]
+    #[query_param(from_proxy = |value: &str| parse_constant(value, "refresh_token"))]
+    grant_type: &'static str,
+    #[query_param(proxy = RefreshToken::expose_secret)]
+    pub refresh_token: RefreshToken,
+}
+
+impl From<RefreshTokenRequestQuery> for Url {
+    fn from(query: RefreshTokenRequestQuery) -> Url {
+        let mut url = Url::parse(AUTHORIZE_TOKEN_REQUEST_URL).unwrap();
+        url.set_query(Some(&query.to_string()));
+        url
+    }
+}
+
 /// [Authorization code grant flow][0] during the [first][1] step.
 ///
 /// [0]: https://dev.twitch.tv/docs/authentication/getting-tokens-oauth/#authorization-code-grant-flow
@@ -70,7 +107,7 @@ impl From<AuthTokenRequestQuery> for Url {
 ///
 /// [`AuthCodeAllowed`]: crate::response::AuthCodeAllowed
 /// [`AuthCodeDenied`]: crate::response::AuthCodeDenied
-#[derive(Serialize, Deserialize, TypedBuilder, QueryParams)]
+#[derive(Debug, Serialize, Deserialize, TypedBuilder, QueryParams)]
 pub struct AuthCodeQuery {
     pub client_id: ClientId,
     #[builder(setter(strip_bool))]
@@ -78,11 +115,12 @@ pub struct AuthCodeQuery {
     pub force_verify: bool,
     pub redirect_uri: String,
     #[builder(default = "code", setter(skip))]
+    #[query_param(from_proxy = |value: &str| parse_constant(value, "code"))]
     response_type: &'static str,
-    #[query_param(skip_if = Vec::is_empty, proxy = percent_encode)]
+    #[query_param(skip_if = Scopes::is_empty)]
     pub scope: Scopes,
     #[builder(default, setter(strip_option))]
-    #[query_param(skip_if = Option::is_none, proxy = unwrap_option)]
+    #[query_param(proxy = CsrfState::expose_secret)]
     pub state: Option<CsrfState>,
 }
 
@@ -94,6 +132,35 @@ impl From<&AuthCodeQuery> for Url {
     }
 }
 
+/// [Validate a token][0], either a user access token or an app access token.
+///
+/// [0]: https://dev.twitch.tv/docs/authentication/validate-tokens/
+///
+/// This is a `GET` request to [`ValidateTokenRequest::url`], with the token
+/// in the header given by [`ValidateTokenRequest::authorization`].
+/// A valid token is answered with [`ValidateTokenAllowed`], and an invalid
+/// token with the status `401 Unauthorized`.
+///
+/// [`ValidateTokenAllowed`]: crate::response::ValidateTokenAllowed
+#[derive(Debug, Clone)]
+pub struct ValidateTokenRequest {
+    pub access_token: AccessToken,
+}
+
+impl ValidateTokenRequest {
+    pub fn url() -> Url {
+        Url::parse(VALIDATE_TOKEN_REQUEST_URL).unwrap()
+    }
+
+    /// The name and value of the `Authorization` header.
+    pub fn authorization(&self) -> (&'static str, String) {
+        (
+            "Authorization",
+            format!("OAuth {}", self.access_token.expose_secret()),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,7 +168,8 @@ mod tests {
 
     #[test]
     fn test_display_query() {
-        let compare = "client_id=oogabooga&redirect_uri=https://localhost:8083&response_type=code&scope=analytics%3Aread%3Aextensions%20channel%3Abot";
+        let compare = "client_id=oogabooga&redirect_uri=https%3A%2F%2Flocalhost%3A8083&\
+                       response_type=code&scope=analytics%3Aread%3Aextensions%20channel%3Abot";
         let data = AuthCodeQuery::builder()
             .client_id("oogabooga".into())
             .redirect_uri("https://localhost:8083".into())
@@ -112,4 +180,96 @@ mod tests {
             .build();
         assert_eq!(compare, data.to_string());
     }
+
+    #[test]
+    fn test_display_query_encoding() {
+        #[derive(QueryParams)]
+        struct Query {
+            #[query_param(skip_if = |x: &bool| !x)]
+            first: bool,
+            #[query_param(rename = "redirect_uri")]
+            uri: String,
+            #[query_param(raw)]
+            encoded: String,
+        }
+
+        let data = Query {
+            first: false,
+            uri: "https://localhost:8083/?a=b&c#d".to_owned(),
+            encoded: "a%20b".to_owned(),
+        };
+        let query = data.to_string();
+        assert_eq!(
+            query,
+            "redirect_uri=https%3A%2F%2Flocalhost%3A8083%2F%3Fa%3Db%26c%23d&encoded=a%20b"
+        );
+
+        let parsed = query.parse::<Query>().unwrap();
+        assert_eq!(parsed.uri, data.uri);
+        assert_eq!(parsed.encoded, "a b");
+    }
+
+    #[test]
+    fn test_repeated_query_params() {
+        #[derive(Debug, PartialEq, QueryParams)]
+        struct Query {
+            id: Vec<u32>,
+            login: Option<String>,
+            first: Option<u8>,
+        }
+
+        let data = Query {
+            id: vec![1, 2],
+            login: Some("foo bar".to_owned()),
+            first: None,
+        };
+        assert_eq!(data.to_string(), "id=1&id=2&login=foo%20bar");
+        assert_eq!(
+            data.to_pairs(),
+            [
+                ("id", "1".to_owned()),
+                ("id", "2".to_owned()),
+                ("login", "foo bar".to_owned()),
+            ]
+        );
+
+        let mut url = Url::parse("https://api.twitch.tv/helix/users").unwrap();
+        data.append_to(&mut url);
+        assert_eq!(url.query(), Some("id=1&id=2&login=foo+bar"));
+        assert_eq!(url.query().unwrap().parse::<Query>().unwrap(), data);
+
+        let empty = "".parse::<Query>().unwrap();
+        assert!(empty.id.is_empty() && empty.login.is_none() && empty.first.is_none());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = "client_id=oogabooga&redirect_uri=https%3A%2F%2Flocalhost%3A8083&\
+                     response_type=code&scope=analytics%3Aread%3Aextensions+channel%3Abot&\
+                     state=abc";
+        let data = query.parse::<AuthCodeQuery>().unwrap();
+        assert_eq!(data.client_id.as_str(), "oogabooga");
+        assert!(!data.force_verify);
+        assert_eq!(data.redirect_uri, "https://localhost:8083");
+        assert_eq!(
+            data.scope,
+            Scopes::from_iter([Scope::AnalyticsReadExtensions, Scope::ChannelBot])
+        );
+        assert_eq!(data.state, Some(CsrfState::from("abc")));
+
+        let error = "client_id=oogabooga&response_type=token"
+            .parse::<AuthCodeQuery>()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid value for query parameter `response_type`: expected `code`"
+        );
+        let error = "client_id=oogabooga&response_type=code"
+            .parse::<AuthCodeQuery>()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ParseAuthCodeQueryError::Missing("redirect_uri")
+        ));
+    }
 }
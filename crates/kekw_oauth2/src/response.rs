@@ -1,17 +1,20 @@
 use std::fmt;
 
+use kekw_macros::FromQueryParams;
 use serde::{Deserialize, Serialize};
 
-use crate::types::{AccessToken, AuthCode, CsrfState, RefreshToken, Scopes};
+use crate::types::{AccessToken, AuthCode, ClientId, CsrfState, RefreshToken, Scopes};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Query parameters of the redirect from Twitch when the user authorizes your app.
+#[derive(Serialize, Deserialize, Debug, FromQueryParams)]
 pub struct AuthCodeAllowed {
     pub code: AuthCode,
     pub scope: Scopes,
     pub state: Option<CsrfState>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Query parameters of the redirect from Twitch when the user denies your app.
+#[derive(Serialize, Deserialize, Debug, FromQueryParams)]
 pub struct AuthCodeDenied {
     pub error: String,
     pub error_description: String,
@@ -51,3 +54,30 @@ pub struct AuthTokenAllowed {
     pub scope: Scopes,
     pub token_type: String,
 }
+
+/// Body of a successful response when [validating a token][0].
+///
+/// [0]: https://dev.twitch.tv/docs/authentication/validate-tokens/
+///
+/// ```json
+/// {
+///   "client_id": "wbmytr93xzw8zbg0p1izqyzzc5mbiz",
+///   "login": "twitchdev",
+///   "scopes": [
+///     "channel:read:subscriptions"
+///   ],
+///   "user_id": "141981764",
+///   "expires_in": 5520838
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidateTokenAllowed {
+    pub client_id: ClientId,
+    /// Absent for app access tokens.
+    pub login: Option<String>,
+    /// Absent for app access tokens.
+    pub scopes: Option<Scopes>,
+    /// Absent for app access tokens.
+    pub user_id: Option<String>,
+    pub expires_in: u64,
+}
@@ -1,9 +1,10 @@
 use async_net::{AsyncToSocketAddrs, IpAddr, SocketAddr, TcpListener, TcpStream};
 use futures_lite::io::{self, BufReader};
 use futures_lite::{AsyncBufReadExt as _, AsyncWriteExt as _, StreamExt as _};
+use tracing::Instrument as _;
 
-use crate::response::{AuthCodeAllowed, AuthCodeDenied};
-use crate::types::CsrfStateRef;
+use crate::response::{AuthCodeAllowed, AuthCodeDenied, ParseAuthCodeAllowedError};
+use crate::types::CsrfState;
 
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
@@ -13,8 +14,8 @@ pub enum Error {
     #[error("{0}")]
     AuthDenied(AuthCodeDenied),
     #[error("{0}")]
-    ParseQuery(#[from] serde_qs::Error),
-    #[error("{0}: {1:?}")]
+    ParseQuery(#[from] ParseAuthCodeAllowedError),
+    #[error("{0}")]
     InvalidCsrfState(&'static str, AuthCodeAllowed),
 }
 
@@ -26,12 +27,28 @@ pub fn make_socket_addrs(ips: &[impl AsRef<str>], port: u16) -> Vec<SocketAddr>
         .collect()
 }
 
+/// Listen on `addrs` for the redirect from Twitch, and return the authorization
+/// code once it arrives, after checking that its CSRF state matches `state`.
+///
+/// The query parameters, which contain the code and the state, are never logged.
 pub async fn await_auth_code(
     addrs: impl AsyncToSocketAddrs,
-    state: Option<&CsrfStateRef>,
+    state: Option<&CsrfState>,
     max_tries: usize,
 ) -> Result<AuthCodeAllowed, Error> {
     let listener = TcpListener::bind(addrs).await?;
+    let span = tracing::info_span!("await_auth_code", addr = ?listener.local_addr().ok());
+    receive_auth_code(listener, state, max_tries)
+        .instrument(span)
+        .await
+}
+
+async fn receive_auth_code(
+    listener: TcpListener,
+    state: Option<&CsrfState>,
+    max_tries: usize,
+) -> Result<AuthCodeAllowed, Error> {
+    tracing::info!("listening for the authorization code redirect");
     let mut incoming = listener.incoming();
 
     let mut attempt = 0;
@@ -44,19 +61,20 @@ pub async fn await_auth_code(
             .into());
         };
         let mut stream = stream?;
+        tracing::debug!(peer = ?stream.peer_addr().ok(), "accepted a connection");
         let query = match receive_query_params(&mut stream).await {
             Ok(query) => {
                 write_200_ok(&mut stream).await?;
                 query
             }
             Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                tracing::debug!(error = %e, "ignored a request without a redirect query");
                 write_418_im_a_teapot(&mut stream).await?;
                 continue;
             }
             Err(e) if attempt < max_tries => {
-                dbg!(e);
+                tracing::warn!(error = %e, attempt, max_tries, "failed to read a request");
                 attempt += 1;
-                // im_a_teapot_418(&mut stream).await?;
                 continue;
             }
             Err(e) => break Err(e.into()),
@@ -64,7 +82,13 @@ pub async fn await_auth_code(
         let res = match parse_query_params(query) {
             Ok(allow) => Ok(allow),
             Err(Error::AuthDenied(e)) if attempt < max_tries => {
-                dbg!(e);
+                tracing::warn!(
+                    error = %e.error,
+                    description = %e.error_description,
+                    attempt,
+                    max_tries,
+                    "the user denied authorization",
+                );
                 attempt += 1;
                 continue;
             }
@@ -73,7 +97,7 @@ pub async fn await_auth_code(
         break res;
     }?;
 
-    match (state, &res.state) {
+    let res = match (state, &res.state) {
         (Some(sent), Some(received)) if sent == received => Ok(res),
         (Some(_), Some(_)) => Err(Error::InvalidCsrfState(
             "the API responded with an invalid CSRF token",
@@ -88,7 +112,13 @@ pub async fn await_auth_code(
             res,
         )),
         (None, None) => Ok(res),
+    };
+    match &res {
+        Ok(_) => tracing::info!("received the authorization code"),
+        // Neither state is logged, only that they do not match.
+        Err(e) => tracing::warn!(error = %e, "rejected the authorization code"),
     }
+    res
 }
 
 #[rustfmt::skip]
@@ -151,7 +181,13 @@ async fn receive_query_params(stream: &mut TcpStream) -> io::Result<String> {
         }
     }
 
-    // dbg!(headers);
+    // Only the names, because values such as cookies may be sensitive.
+    tracing::trace!(
+        method = _method,
+        path = path_and_query.split('?').next(),
+        headers = ?headers.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        "received a request",
+    );
 
     let (_path, query) = path_and_query
         .split_once('?')
@@ -166,18 +202,14 @@ async fn receive_query_params(stream: &mut TcpStream) -> io::Result<String> {
     Ok(query.to_owned())
 }
 
-/// Incomplete: requires deserializing to be replaced with custom `Deserializer``.
-/// Perhaps also convert the `FromStr` impl on `QueryString` derives to a serde `Serializer`.
-/// When I do this, the `Deserializer` must be custom, and respect attributes on fields of structures, enums, and tuples.
-/// There needs to be a `deserialize_proxy = fn(T) -> String` attribute that allows
-/// affecting the serializer's operation. Perhaps an automatically-derived `QueryStrings` trait,
-/// that implements `Serialize` for `QueryStrings`?
+/// Parse the query parameters of the redirect from Twitch, which either
+/// contain an authorization code, or the reason that it was denied.
 fn parse_query_params(query: impl AsRef<str>) -> Result<AuthCodeAllowed, Error> {
-    if let Ok(allowed) = serde_qs::from_str(query.as_ref()) {
-        Ok(allowed)
-    } else {
-        Err(Error::AuthDenied(serde_qs::from_str::<AuthCodeDenied>(
-            query.as_ref(),
-        )?))
+    match query.as_ref().parse::<AuthCodeAllowed>() {
+        Ok(allowed) => Ok(allowed),
+        Err(e) => match query.as_ref().parse::<AuthCodeDenied>() {
+            Ok(denied) => Err(Error::AuthDenied(denied)),
+            Err(_) => Err(Error::ParseQuery(e)),
+        },
     }
 }
@@ -1,4 +1,5 @@
 pub mod scopes;
+mod secret;
 
 #[rustfmt::skip]
 #[doc(inline)]
@@ -6,14 +7,18 @@ pub use scopes::*;
 
 use aliri_braid::braid;
 
+use self::secret::secret_string;
+
 #[braid(secret, serde)]
 pub struct ClientId;
 
-#[braid(secret, serde)]
-pub struct ClientSecret;
+secret_string! {
+    pub struct ClientSecret;
+}
 
-#[braid(secret, serde)]
-pub struct CsrfState;
+secret_string! {
+    pub struct CsrfState;
+}
 
 impl CsrfState {
     pub fn new_random() -> Self {
@@ -28,11 +33,14 @@ impl CsrfState {
     }
 }
 
-#[braid(secret, serde)]
-pub struct AuthCode;
+secret_string! {
+    pub struct AuthCode;
+}
 
-#[braid(secret, serde)]
-pub struct AccessToken;
+secret_string! {
+    pub struct AccessToken;
+}
 
-#[braid(secret, serde)]
-pub struct RefreshToken;
+secret_string! {
+    pub struct RefreshToken;
+}
@@ -1,16 +1,27 @@
 //! Adapted from:
 //! <https://github.com/twitch-rs/twitch_oauth2/blob/e8bfe4e80e4c5a53f1b0ed77cf85db0fcde3aa31/src/scopes.rs>
 
-use std::fmt;
 use std::str::FromStr;
+use std::{fmt, ops};
 
 use kekw_macros::{
-    DebugExprs, DerefNewType, DeserializeFromStr, DisplayStrings, NewTypeFrom, VariantFromStr,
+    DebugExprs, DeserializeFromStr, DisplayStrings, SerializeToStr, VariantDocs, VariantFromStr,
     VariantStrings,
 };
 
 #[derive(
-    Copy, Clone, DebugExprs, DisplayStrings, VariantStrings, VariantFromStr, DeserializeFromStr,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    DebugExprs,
+    DisplayStrings,
+    VariantStrings,
+    VariantFromStr,
+    VariantDocs,
+    SerializeToStr,
+    DeserializeFromStr,
 )]
 #[non_exhaustive]
 pub enum Scope {
@@ -44,7 +55,7 @@ pub enum Scope {
     /// Manage a channel’s polls.
     #[static_str("channel:manage:polls")]
     ChannelManagePolls,
-    /// Manage of channel’s Channel Points Predictions
+    /// Manage a channel’s Channel Points Predictions.
     #[static_str("channel:manage:predictions")]
     ChannelManagePredictions,
     /// Manage a channel raiding another channel.
@@ -116,7 +127,7 @@ pub enum Scope {
     /// Manage messages held for review by AutoMod in channels where you are a moderator.
     #[static_str("moderator:manage:automod")]
     ModeratorManageAutoMod,
-    /// Manage a broadcaster’s AutoMod settings
+    /// Manage a broadcaster’s AutoMod settings.
     #[static_str("moderator:manage:automod_settings")]
     ModeratorManageAutomodSettings,
     /// Ban and unban users.
@@ -125,10 +136,10 @@ pub enum Scope {
     /// Manage a broadcaster’s list of blocked terms.
     #[static_str("moderator:manage:blocked_terms")]
     ModeratorManageBlockedTerms,
-    /// Delete chat messages in channels where you have the moderator role
+    /// Delete chat messages in channels where you have the moderator role.
     #[static_str("moderator:manage:chat_messages")]
     ModeratorManageChatMessages,
-    /// View a broadcaster’s chat room settings.
+    /// Manage a broadcaster’s chat room settings.
     #[static_str("moderator:manage:chat_settings")]
     ModeratorManageChatSettings,
     /// Manage a broadcaster’s Shield Mode status.
@@ -170,7 +181,7 @@ pub enum Scope {
     /// Manage the block list of a user.
     #[static_str("user:manage:blocked_users")]
     UserManageBlockedUsers,
-    /// Update the color used for the user’s name in chat.Update User Chat Color
+    /// Update the color used for the user’s name in chat.
     #[static_str("user:manage:chat_color")]
     UserManageChatColor,
     /// Read whispers that you send and receive, and send whispers on your behalf.
@@ -208,8 +219,345 @@ pub enum Scope {
     WhispersRead,
 }
 
-#[derive(Clone, Debug, DerefNewType, NewTypeFrom)]
-pub struct Scopes(#[deref(mut)] Vec<Scope>);
+impl Scope {
+    /// A human-readable description of what this scope grants.
+    /// This is the same text as the documentation of each variant.
+    pub const fn description(&self) -> &'static str {
+        self.docs()
+    }
+
+    /// The kind of resource that this scope grants access to.
+    pub const fn category(&self) -> ScopeCategory {
+        match self {
+            Scope::AnalyticsReadExtensions | Scope::AnalyticsReadGames => ScopeCategory::Analytics,
+            Scope::BitsRead => ScopeCategory::Bits,
+            Scope::ChannelBot
+            | Scope::ChannelEditCommercial
+            | Scope::ChannelManageAds
+            | Scope::ChannelManageBroadcast
+            | Scope::ChannelManageExtensions
+            | Scope::ChannelManageModerators
+            | Scope::ChannelManagePolls
+            | Scope::ChannelManagePredictions
+            | Scope::ChannelManageRaids
+            | Scope::ChannelManageRedemptions
+            | Scope::ChannelManageSchedule
+            | Scope::ChannelManageVideos
+            | Scope::ChannelManageVips
+            | Scope::ChannelModerate
+            | Scope::ChannelReadAds
+            | Scope::ChannelReadCharity
+            | Scope::ChannelReadEditors
+            | Scope::ChannelReadGoals
+            | Scope::ChannelReadHypeTrain
+            | Scope::ChannelReadPolls
+            | Scope::ChannelReadPredictions
+            | Scope::ChannelReadRedemptions
+            | Scope::ChannelReadStreamKey
+            | Scope::ChannelReadSubscriptions
+            | Scope::ChannelReadVips => ScopeCategory::Channel,
+            Scope::ChatEdit | Scope::ChatRead => ScopeCategory::Chat,
+            Scope::ClipsEdit => ScopeCategory::Clips,
+            Scope::ModerationRead
+            | Scope::ModeratorManageAnnouncements
+            | Scope::ModeratorManageAutoMod
+            | Scope::ModeratorManageAutomodSettings
+            | Scope::ModeratorManageBannedUsers
+            | Scope::ModeratorManageBlockedTerms
+            | Scope::ModeratorManageChatMessages
+            | Scope::ModeratorManageChatSettings
+            | Scope::ModeratorManageShieldMode
+            | Scope::ModeratorManageShoutouts
+            | Scope::ModeratorReadAutomodSettings
+            | Scope::ModeratorReadBlockedTerms
+            | Scope::ModeratorReadChatSettings
+            | Scope::ModeratorReadChatters
+            | Scope::ModeratorReadFollowers
+            | Scope::ModeratorReadShieldMode
+            | Scope::ModeratorReadShoutouts => ScopeCategory::Moderation,
+            Scope::UserBot
+            | Scope::UserEdit
+            | Scope::UserEditBroadcast
+            | Scope::UserManageBlockedUsers
+            | Scope::UserManageChatColor
+            | Scope::UserManageWhispers
+            | Scope::UserReadBlockedUsers
+            | Scope::UserReadBroadcast
+            | Scope::UserReadChat
+            | Scope::UserReadEmail
+            | Scope::UserReadFollows
+            | Scope::UserReadModeratedChannels
+            | Scope::UserReadSubscriptions
+            | Scope::UserWriteChat => ScopeCategory::User,
+            Scope::WhispersEdit | Scope::WhispersRead => ScopeCategory::Whispers,
+        }
+    }
+
+    /// Whether this scope only allows reading data, or also allows
+    /// making changes and acting on behalf of the user.
+    pub const fn access(&self) -> ScopeAccess {
+        match self {
+            Scope::AnalyticsReadExtensions
+            | Scope::AnalyticsReadGames
+            | Scope::BitsRead
+            | Scope::ChannelReadAds
+            | Scope::ChannelReadCharity
+            | Scope::ChannelReadEditors
+            | Scope::ChannelReadGoals
+            | Scope::ChannelReadHypeTrain
+            | Scope::ChannelReadPolls
+            | Scope::ChannelReadPredictions
+            | Scope::ChannelReadRedemptions
+            | Scope::ChannelReadStreamKey
+            | Scope::ChannelReadSubscriptions
+            | Scope::ChannelReadVips
+            | Scope::ChatRead
+            | Scope::ModerationRead
+            | Scope::ModeratorReadAutomodSettings
+            | Scope::ModeratorReadBlockedTerms
+            | Scope::ModeratorReadChatSettings
+            | Scope::ModeratorReadChatters
+            | Scope::ModeratorReadFollowers
+            | Scope::ModeratorReadShieldMode
+            | Scope::ModeratorReadShoutouts
+            | Scope::UserReadBlockedUsers
+            | Scope::UserReadBroadcast
+            | Scope::UserReadChat
+            | Scope::UserReadEmail
+            | Scope::UserReadFollows
+            | Scope::UserReadModeratedChannels
+            | Scope::UserReadSubscriptions
+            | Scope::WhispersRead => ScopeAccess::Read,
+            _ => ScopeAccess::Write,
+        }
+    }
+
+    /// Whether Twitch has deprecated this scope, see [`Scope::DEPRECATED`].
+    pub const fn is_deprecated(&self) -> bool {
+        let mut i = 0;
+        while i < Self::DEPRECATED.len() {
+            if Self::DEPRECATED[i] as usize == *self as usize {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Scopes that the [Twitch scope reference](https://dev.twitch.tv/docs/authentication/scopes/)
+    /// marks as deprecated. It is empty while no variant of [`Scope`] is.
+    pub const DEPRECATED: &'static [Scope] = &[];
+}
+
+/// The kind of resource that a [`Scope`] grants access to.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, DebugExprs, DisplayStrings, VariantStrings)]
+#[non_exhaustive]
+pub enum ScopeCategory {
+    #[static_str("analytics")]
+    Analytics,
+    #[static_str("bits")]
+    Bits,
+    #[static_str("channel")]
+    Channel,
+    #[static_str("chat")]
+    Chat,
+    #[static_str("clips")]
+    Clips,
+    #[static_str("moderation")]
+    Moderation,
+    #[static_str("user")]
+    User,
+    #[static_str("whispers")]
+    Whispers,
+}
+
+/// Whether a [`Scope`] grants read-only access, or write access.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, DebugExprs, DisplayStrings, VariantStrings)]
+pub enum ScopeAccess {
+    #[static_str("read")]
+    Read,
+    #[static_str("write")]
+    Write,
+}
+
+/// The number of `u64` words needed to hold one bit for every [`Scope`].
+const SCOPES_WORDS: usize = Scope::COUNT.div_ceil(u64::BITS as usize);
+
+/// A set of [`Scope`]s, stored as a bitset indexed by the discriminant of each variant.
+///
+/// Set operations touch a fixed number of words, and the type is `Copy`.
+///
+/// Each scope is held at most once, and iteration yields scopes in the order
+/// that they are declared on [`Scope`], regardless of insertion order.
+/// `Display` and `Serialize` use the same order, so `"chat:read bits:read chat:read"`
+/// is formatted as `"bits:read chat:read"`. Earlier versions kept the
+/// scopes in a `Vec`, and formatted them in insertion order with duplicates.
+/// Use [`Self::iter`] or [`Self::to_vec`] where that `Vec` was dereferenced.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, SerializeToStr, DeserializeFromStr)]
+#[deserialize_from_str(seq = Scope)]
+pub struct Scopes([u64; SCOPES_WORDS]);
+
+impl Scopes {
+    /// A set with no scopes.
+    pub const fn new() -> Self {
+        Self([0; SCOPES_WORDS])
+    }
+
+    /// A set of every scope known to this crate.
+    pub fn all() -> Self {
+        Scope::iter().collect()
+    }
+
+    #[inline]
+    const fn position(scope: Scope) -> (usize, u64) {
+        let index = scope as usize;
+        (
+            index / u64::BITS as usize,
+            1 << (index % u64::BITS as usize),
+        )
+    }
+
+    /// Add a scope to the set, returning `true` if it was not already present.
+    pub fn insert(&mut self, scope: Scope) -> bool {
+        let (word, bit) = Self::position(scope);
+        let absent = self.0[word] & bit == 0;
+        self.0[word] |= bit;
+        absent
+    }
+
+    /// Remove a scope from the set, returning `true` if it was present.
+    pub fn remove(&mut self, scope: Scope) -> bool {
+        let (word, bit) = Self::position(scope);
+        let present = self.0[word] & bit != 0;
+        self.0[word] &= !bit;
+        present
+    }
+
+    /// Whether the set holds `scope`.
+    pub const fn contains(&self, scope: Scope) -> bool {
+        let (word, bit) = Self::position(scope);
+        self.0[word] & bit != 0
+    }
+
+    /// Whether the set holds no scopes.
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    /// The number of scopes in the set.
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Iterate over the scopes in this set, in declaration order.
+    pub fn iter(&self) -> impl Iterator<Item = Scope> + '_ {
+        Scope::iter().filter(|scope| self.contains(*scope))
+    }
+
+    /// The scopes in this set, in declaration order.
+    pub fn to_vec(&self) -> Vec<Scope> {
+        self.iter().collect()
+    }
+
+    fn zip_with(self, other: Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        let mut words = self.0;
+        for (word, other) in words.iter_mut().zip(other.0) {
+            *word = f(*word, other);
+        }
+        Self(words)
+    }
+
+    /// Scopes that are in either `self` or `other`.
+    pub fn union(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    /// Scopes that are in both `self` and `other`.
+    pub fn intersection(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    /// Scopes that are in `self` but not in `other`.
+    pub fn difference(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    /// Whether every scope in `self` is also in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(*other).is_empty()
+    }
+
+    /// Whether every scope in `other` is also in `self`.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+}
+
+impl ops::BitOr for Scopes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl ops::BitOrAssign for Scopes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+impl ops::BitAnd for Scopes {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl ops::BitAndAssign for Scopes {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = self.intersection(rhs);
+    }
+}
+
+impl ops::Sub for Scopes {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+impl ops::SubAssign for Scopes {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.difference(rhs);
+    }
+}
+
+impl From<Scope> for Scopes {
+    fn from(scope: Scope) -> Self {
+        Self::from_iter([scope])
+    }
+}
+
+impl From<Vec<Scope>> for Scopes {
+    fn from(scopes: Vec<Scope>) -> Self {
+        Self::from_iter(scopes)
+    }
+}
+
+impl From<Scopes> for Vec<Scope> {
+    fn from(scopes: Scopes) -> Self {
+        scopes.to_vec()
+    }
+}
+
+impl fmt::Debug for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
 
 impl fmt::Display for Scopes {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -230,7 +578,20 @@ impl FromIterator<Scope> for Scopes {
     where
         T: IntoIterator<Item = Scope>,
     {
-        Self(iter.into_iter().collect())
+        let mut scopes = Self::new();
+        scopes.extend(iter);
+        scopes
+    }
+}
+
+impl Extend<Scope> for Scopes {
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = Scope>,
+    {
+        for scope in iter {
+            self.insert(scope);
+        }
     }
 }
 
@@ -244,59 +605,101 @@ impl FromStr for Scopes {
     }
 }
 
-mod serde_impl {
-    use serde::de::Visitor;
-    use serde::{Deserialize, Serialize};
-
+#[cfg(test)]
+mod tests {
     use super::*;
 
-    impl Serialize for Scopes {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: serde::Serializer,
-        {
-            serializer.serialize_str(&self.to_string())
+    #[test]
+    fn test_scope_catalog() {
+        assert_eq!(Scope::iter().count(), Scope::COUNT);
+        assert_eq!(Scope::ALL[0].as_str(), "analytics:read:extensions");
+        for scope in Scope::iter() {
+            assert_eq!(Some(scope), scope.as_str().parse().ok());
+            assert!(!scope.description().is_empty());
+        }
+        assert_eq!(Scope::ChatRead.category(), ScopeCategory::Chat);
+        assert_eq!(
+            Scope::ModeratorReadChatters.category(),
+            ScopeCategory::Moderation
+        );
+        assert_eq!(Scope::ModerationRead.access(), ScopeAccess::Read);
+        assert_eq!(Scope::ChannelManagePolls.access(), ScopeAccess::Write);
+        assert!(!Scope::WhispersRead.is_deprecated());
+        for scope in Scope::iter() {
+            assert_eq!(scope.is_deprecated(), Scope::DEPRECATED.contains(&scope));
+        }
+        for scope in Scope::iter() {
+            let is_read = scope.as_str().split(':').any(|part| part == "read");
+            assert_eq!(scope.access() == ScopeAccess::Read, is_read);
         }
+        assert_eq!(
+            Scope::UserManageChatColor.description(),
+            "Update the color used for the user’s name in chat."
+        );
     }
 
-    struct ScopesVisitor;
+    #[test]
+    fn test_parse_scope_error() {
+        let error = "chat:reed".parse::<Scope>().unwrap_err();
+        assert_eq!(error.input(), "chat:reed");
+        assert_eq!(error.suggestion(), Some("chat:read"));
+        assert_eq!(error.expected().len(), Scope::COUNT);
+        assert_eq!(
+            error.to_string(),
+            "string did not match any variant: `chat:reed`, did you mean `chat:read`?"
+        );
+        assert_eq!("xyz".parse::<Scope>().unwrap_err().suggestion(), None);
+    }
 
-    impl<'de> Visitor<'de> for ScopesVisitor {
-        type Value = Scopes;
+    #[test]
+    fn test_scopes_set_operations() {
+        use Scope::*;
 
-        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-        where
-            E: serde::de::Error,
-        {
-            v.parse().map_err(|e| E::custom(e))
-        }
+        let read = Scopes::from_iter([ChatRead, ModerationRead, UserReadEmail]);
+        let chat = Scopes::from_iter([ChatEdit, ChatRead]);
 
-        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-        where
-            A: serde::de::SeqAccess<'de>,
-        {
-            let mut vec = Vec::new();
-            while let Some(elem) = seq.next_element()? {
-                vec.push(elem);
-            }
-            Ok(vec.into())
-        }
-
-        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            write!(
-                f,
-                "a string or sequence of strings that can be parsed by `<{} as FromStr>::from_str`",
-                stringify!(Scopes)
-            )
-        }
+        assert_eq!(read.len(), 3);
+        assert!(read.contains(ChatRead) && !read.contains(ChatEdit));
+        assert_eq!(read & chat, Scopes::from(ChatRead));
+        assert_eq!((read | chat).len(), 4);
+        assert_eq!(
+            read - chat,
+            Scopes::from_iter([ModerationRead, UserReadEmail])
+        );
+        assert!(Scopes::from(ChatRead).is_subset(&chat));
+        assert!(!read.is_subset(&chat));
+        assert!(Scopes::all().is_superset(&read));
+        assert!(Scopes::new().is_empty());
+        assert_eq!(
+            Scopes::from(vec![ChatRead, BitsRead, ChatRead]).to_vec(),
+            [BitsRead, ChatRead]
+        );
     }
 
-    impl<'de> Deserialize<'de> for Scopes {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::de::Deserializer<'de>,
-        {
-            deserializer.deserialize_any(ScopesVisitor)
-        }
+    #[test]
+    fn test_scopes_formats() {
+        let scopes: Scopes = "whispers:read chat:read  analytics:read:games"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            scopes.to_string(),
+            "analytics:read:games chat:read whispers:read"
+        );
+        assert_eq!(
+            serde_json::from_str::<Scopes>(
+                r#"["chat:read", "whispers:read", "analytics:read:games"]"#
+            )
+            .unwrap(),
+            scopes
+        );
+        assert_eq!(
+            serde_json::to_string(&scopes).unwrap(),
+            r#""analytics:read:games chat:read whispers:read""#
+        );
+        assert_eq!(
+            serde_json::to_string(&Scope::ChatRead).unwrap(),
+            r#""chat:read""#
+        );
+        assert!(serde_json::from_str::<Scope>(r#"["chat:read"]"#).is_err());
     }
 }
@@ -0,0 +1,107 @@
+//! Newtypes for credentials that must not be leaked.
+//!
+//! Unlike the braids used for public identifiers, these do not implement
+//! `Display`, `Deref` or `AsRef<str>`. The only way to read the value is
+//! to call `expose_secret()`, so that every use of a credential is explicit.
+//!
+//! - `Debug` prints the type name and a placeholder, never the value.
+//! - Equality is checked in constant time with [`subtle`].
+//! - `Hash` feeds the value itself to the hasher, so that credentials can be
+//!   used as map keys. The standard `HashMap` uses a randomly keyed hasher,
+//!   but a hasher whose state can be observed would reveal the value.
+//! - The string is overwritten with zeros when it is dropped.
+//!
+//! Serialization is transparent, because the Twitch API expects these values
+//! in plain text. Take care when serializing into something that may be logged.
+
+macro_rules! secret_string {
+    ($(#[$meta:meta])* $vis:vis struct $ident:ident;) => {
+        $(#[$meta])*
+        #[derive(Clone, ::serde::Serialize, ::serde::Deserialize)]
+        #[serde(transparent)]
+        $vis struct $ident(::std::string::String);
+
+        impl $ident {
+            pub fn new(secret: ::std::string::String) -> Self {
+                Self(secret)
+            }
+
+            /// Borrow the secret value.
+            /// Avoid passing the result to anything that may be logged.
+            pub fn expose_secret(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl ::std::convert::From<::std::string::String> for $ident {
+            fn from(secret: ::std::string::String) -> Self {
+                Self(secret)
+            }
+        }
+
+        impl ::std::convert::From<&str> for $ident {
+            fn from(secret: &str) -> Self {
+                Self(secret.to_owned())
+            }
+        }
+
+        impl ::std::str::FromStr for $ident {
+            type Err = ::std::convert::Infallible;
+
+            fn from_str(secret: &str) -> ::std::result::Result<Self, Self::Err> {
+                ::std::result::Result::Ok(Self::from(secret))
+            }
+        }
+
+        impl ::std::fmt::Debug for $ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                write!(f, "{}(<redacted>)", ::std::stringify!($ident))
+            }
+        }
+
+        impl ::std::cmp::PartialEq for $ident {
+            fn eq(&self, other: &Self) -> bool {
+                use ::subtle::ConstantTimeEq as _;
+                self.0.as_bytes().ct_eq(other.0.as_bytes()).into()
+            }
+        }
+
+        impl ::std::cmp::Eq for $ident {}
+
+        // Hashes the value itself, as documented on the module.
+        impl ::std::hash::Hash for $ident {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                ::std::hash::Hash::hash(&self.0, state)
+            }
+        }
+
+        impl ::std::ops::Drop for $ident {
+            fn drop(&mut self) {
+                ::zeroize::Zeroize::zeroize(&mut self.0);
+            }
+        }
+    };
+}
+
+pub(crate) use secret_string;
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{AccessToken, CsrfState};
+
+    #[test]
+    fn test_secret_redaction() {
+        let token = AccessToken::from("rfx2uswqe8l4g1mkagrvg5tv0ks3");
+        assert_eq!(format!("{token:?}"), "AccessToken(<redacted>)");
+        assert_eq!(format!("{token:#?}"), "AccessToken(<redacted>)");
+        assert_eq!(token.expose_secret(), "rfx2uswqe8l4g1mkagrvg5tv0ks3");
+    }
+
+    #[test]
+    fn test_secret_equality() {
+        let state = CsrfState::new_random();
+        assert_eq!(state, state.clone());
+        assert_ne!(state, CsrfState::new_random());
+        assert_ne!(CsrfState::from("abc"), CsrfState::from("abcd"));
+    }
+}
@@ -0,0 +1,443 @@
+//! Twitch requires applications to [validate][0] the tokens they use
+//! when they start, and every hour thereafter, and to stop using tokens that
+//! have been revoked.
+//!
+//! Keep tokens in a [`ValidatedTokens`] store, and hand out tokens from it.
+//! Then poll the stream returned by [`TokenValidator::run`] in a background task.
+//! Tokens that fail validation are marked as revoked, and the store no longer
+//! returns them. A `HelixClient` refuses to send them when its token source is
+//! wrapped in `kekw_client::tokens::Validated` with a handle to the store.
+//!
+//! The validator does not send the requests itself. Provide a function that
+//! sends a [`ValidateTokenRequest`] with your HTTP client of choice, and
+//! interprets the response with [`Validation::from_response`].
+//!
+//! [0]: https://dev.twitch.tv/docs/authentication/validate-tokens/
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_io::Timer;
+use futures_lite::{stream, Stream};
+use rand::Rng;
+use typed_builder::TypedBuilder;
+
+use crate::requests::ValidateTokenRequest;
+use crate::response::ValidateTokenAllowed;
+use crate::types::{AccessToken, Scopes};
+
+/// Twitch asks that tokens are validated once per hour.
+pub const DEFAULT_VALIDATION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Spread the validation of many applications over time.
+pub const DEFAULT_VALIDATION_JITTER: Duration = Duration::from_secs(5 * 60);
+/// Passes are never closer together than this, so that a zero interval
+/// does not spin when there are no tokens.
+pub const MIN_VALIDATION_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait before validating a token again, when the endpoint is unavailable.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// How many times to validate a token again, when the endpoint is unavailable.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// The outcome of a request to the validation endpoint.
+#[derive(Debug, Clone)]
+pub enum Validation {
+    Valid(ValidateTokenAllowed),
+    /// The endpoint responded with `401 Unauthorized`.
+    Invalid,
+    /// The endpoint responded with another status, such as `429 Too Many Requests`
+    /// or a server error. Whether the token is valid is unknown.
+    Unavailable {
+        status: u16,
+    },
+}
+
+impl Validation {
+    /// Interpret the status code and body of a response from the validation endpoint.
+    /// Only the body of a `200 OK` response is parsed.
+    pub fn from_response(status: u16, body: &[u8]) -> serde_json::Result<Self> {
+        match status {
+            200 => serde_json::from_slice(body).map(Self::Valid),
+            401 => Ok(Self::Invalid),
+            status => Ok(Self::Unavailable { status }),
+        }
+    }
+}
+
+/// Emitted by [`TokenValidator::run`] for every token it validates.
+#[derive(Debug)]
+pub enum ValidationEvent<K, E> {
+    /// The token is valid, and has the same scopes as before.
+    Valid {
+        key: K,
+        response: ValidateTokenAllowed,
+    },
+    /// The token is valid, but the scopes are not the same as when it was
+    /// last validated. This happens when the user re-authorizes with other scopes.
+    ScopesChanged {
+        key: K,
+        previous: Scopes,
+        response: ValidateTokenAllowed,
+    },
+    /// The token is no longer valid, and has been withheld from the store.
+    Revoked { key: K },
+    /// The endpoint was still unavailable after every retry.
+    /// The token continues to be served.
+    Unavailable { key: K, status: u16 },
+    /// The request could not be completed. The token continues to be served.
+    Failed { key: K, error: E },
+}
+
+#[derive(Debug)]
+struct TokenEntry {
+    token: AccessToken,
+    scopes: Option<Scopes>,
+    revoked: bool,
+}
+
+/// Shared storage for tokens that are validated by a [`TokenValidator`].
+///
+/// Cloning this creates another handle to the same tokens.
+#[derive(Debug)]
+pub struct ValidatedTokens<K> {
+    entries: Arc<Mutex<HashMap<K, TokenEntry>>>,
+}
+
+impl<K> Clone for ValidatedTokens<K> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries),
+        }
+    }
+}
+
+impl<K> Default for ValidatedTokens<K> {
+    fn default() -> Self {
+        Self {
+            entries: Arc::default(),
+        }
+    }
+}
+
+impl<K> ValidatedTokens<K>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a token, replacing any previous token with the same key.
+    /// It will be validated on the next pass of the validator.
+    pub fn insert(&self, key: K, token: AccessToken) {
+        self.entries.lock().unwrap().insert(
+            key,
+            TokenEntry {
+                token,
+                scopes: None,
+                revoked: false,
+            },
+        );
+    }
+
+    pub fn remove(&self, key: &K) -> Option<AccessToken> {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(key)
+            .map(|entry| entry.token)
+    }
+
+    /// Get a token, unless it has been revoked.
+    pub fn get(&self, key: &K) -> Option<AccessToken> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|entry| !entry.revoked)
+            .map(|entry| entry.token.clone())
+    }
+
+    /// The scopes reported when the token was last validated.
+    pub fn scopes(&self, key: &K) -> Option<Scopes> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|entry| !entry.revoked)
+            .and_then(|entry| entry.scopes)
+    }
+
+    pub fn is_revoked(&self, key: &K) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|entry| entry.revoked)
+    }
+
+    /// Whether this token is stored under any key, and has been revoked.
+    /// Use this where the token is at hand, but not its key.
+    pub fn is_token_revoked(&self, token: &AccessToken) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .values()
+            .any(|entry| entry.revoked && entry.token == *token)
+    }
+
+    /// Keys of the tokens that have not been revoked.
+    pub fn keys(&self) -> Vec<K> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| !entry.revoked)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Record the outcome of validating `token`, returning the event to emit.
+    /// Nothing is recorded if the token was replaced or removed in the meantime.
+    fn record<E>(
+        &self,
+        key: K,
+        token: &AccessToken,
+        validation: Validation,
+    ) -> Option<ValidationEvent<K, E>> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get_mut(&key)
+            .filter(|entry| entry.token == *token)?;
+        Some(match validation {
+            Validation::Valid(response) => {
+                let current = response.scopes.unwrap_or_default();
+                match entry.scopes.replace(current) {
+                    Some(previous) if previous != current => {
+                        tracing::info!(%previous, %current, "the scopes of a token changed");
+                        ValidationEvent::ScopesChanged {
+                            key,
+                            previous,
+                            response,
+                        }
+                    }
+                    _ => {
+                        tracing::debug!("a token is valid");
+                        ValidationEvent::Valid { key, response }
+                    }
+                }
+            }
+            Validation::Invalid => {
+                tracing::warn!("a token was revoked");
+                entry.revoked = true;
+                ValidationEvent::Revoked { key }
+            }
+            Validation::Unavailable { status } => {
+                tracing::warn!(status, "the validation endpoint is unavailable");
+                ValidationEvent::Unavailable { key, status }
+            }
+        })
+    }
+}
+
+/// Validates every token in a [`ValidatedTokens`] store on an interval.
+///
+/// Each pass validates the tokens one after another, and then waits for
+/// `interval`, plus or minus a random duration of up to `jitter`, but at
+/// least [`MIN_VALIDATION_INTERVAL`].
+///
+/// When the endpoint is [unavailable](Validation::Unavailable), the token is
+/// validated again after `retry_delay`, up to `max_retries` times.
+#[derive(TypedBuilder)]
+pub struct TokenValidator<K, F> {
+    tokens: ValidatedTokens<K>,
+    /// Sends the request, and interprets the response with [`Validation::from_response`].
+    validate: F,
+    #[builder(default = DEFAULT_VALIDATION_INTERVAL)]
+    interval: Duration,
+    #[builder(default = DEFAULT_VALIDATION_JITTER)]
+    jitter: Duration,
+    #[builder(default = DEFAULT_RETRY_DELAY)]
+    retry_delay: Duration,
+    #[builder(default = DEFAULT_MAX_RETRIES)]
+    max_retries: u32,
+}
+
+struct ValidatorState<K, F> {
+    validator: TokenValidator<K, F>,
+    pending: VecDeque<K>,
+    started: bool,
+}
+
+impl<K, F, Fut, E> TokenValidator<K, F>
+where
+    K: Clone + Eq + Hash,
+    F: FnMut(ValidateTokenRequest) -> Fut,
+    Fut: Future<Output = Result<Validation, E>>,
+{
+    /// Validate every token immediately, and then once per interval.
+    ///
+    /// The stream never ends. Validation only makes progress while the stream
+    /// is polled, so consume it in a task of its own.
+    pub fn run(self) -> impl Stream<Item = ValidationEvent<K, E>> {
+        let state = ValidatorState {
+            validator: self,
+            pending: VecDeque::new(),
+            started: false,
+        };
+        stream::unfold(state, |mut state| async move {
+            loop {
+                let Some(key) = state.pending.pop_front() else {
+                    if state.started {
+                        Timer::after(state.validator.next_delay()).await;
+                    }
+                    state.started = true;
+                    state.pending.extend(state.validator.tokens.keys());
+                    tracing::debug!(tokens = state.pending.len(), "validating tokens");
+                    continue;
+                };
+                let Some(token) = state.validator.tokens.get(&key) else {
+                    continue;
+                };
+                let mut retries = 0;
+                let event = loop {
+                    let request = ValidateTokenRequest {
+                        access_token: token.clone(),
+                    };
+                    match (state.validator.validate)(request).await {
+                        Ok(Validation::Unavailable { status })
+                            if retries < state.validator.max_retries =>
+                        {
+                            retries += 1;
+                            tracing::debug!(status, retries, "retrying the validation of a token");
+                            Timer::after(state.validator.retry_delay).await;
+                        }
+                        Ok(validation) => {
+                            break state.validator.tokens.record(key, &token, validation)
+                        }
+                        Err(error) => {
+                            tracing::warn!("failed to validate a token");
+                            break Some(ValidationEvent::Failed { key, error });
+                        }
+                    }
+                };
+                if let Some(event) = event {
+                    break Some((event, state));
+                }
+            }
+        })
+    }
+
+    fn next_delay(&self) -> Duration {
+        let offset = self.jitter.mul_f64(2.0 * rand::thread_rng().gen::<f64>());
+        (self.interval.saturating_sub(self.jitter) + offset).max(MIN_VALIDATION_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_lite::future::block_on;
+    use futures_lite::StreamExt as _;
+
+    use super::*;
+    use crate::types::{ClientId, Scope};
+
+    fn allowed(scopes: impl IntoIterator<Item = Scope>) -> Validation {
+        Validation::Valid(ValidateTokenAllowed {
+            client_id: ClientId::from("client"),
+            login: Some("twitchdev".to_owned()),
+            scopes: Some(Scopes::from_iter(scopes)),
+            user_id: Some("141981764".to_owned()),
+            expires_in: 3600,
+        })
+    }
+
+    #[test]
+    fn test_token_validator_events() {
+        let tokens = ValidatedTokens::new();
+        tokens.insert("valid", AccessToken::from("valid"));
+        tokens.insert("revoked", AccessToken::from("revoked"));
+
+        let mut passes = 0;
+        let validator = TokenValidator::builder()
+            .tokens(tokens.clone())
+            .validate(|request: ValidateTokenRequest| {
+                let token = request.access_token.expose_secret().to_owned();
+                if token == "valid" {
+                    passes += 1;
+                }
+                let scopes = if passes > 1 {
+                    vec![Scope::ChatRead, Scope::ChatEdit]
+                } else {
+                    vec![Scope::ChatRead]
+                };
+                async move {
+                    match token.as_str() {
+                        "valid" => Ok::<_, ()>(allowed(scopes)),
+                        _ => Ok(Validation::Invalid),
+                    }
+                }
+            })
+            .interval(Duration::ZERO)
+            .jitter(Duration::ZERO)
+            .build();
+
+        let events = block_on(validator.run().take(3).collect::<Vec<_>>());
+
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, ValidationEvent::Valid { key: "valid", .. })));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, ValidationEvent::Revoked { key: "revoked" })));
+        assert!(matches!(
+            events.last(),
+            Some(ValidationEvent::ScopesChanged { key: "valid", previous, .. })
+                if *previous == Scopes::from(Scope::ChatRead)
+        ));
+
+        assert!(tokens.get(&"valid").is_some());
+        assert!(tokens.get(&"revoked").is_none());
+        assert!(tokens.is_revoked(&"revoked"));
+        assert!(tokens.is_token_revoked(&AccessToken::from("revoked")));
+        assert!(!tokens.is_token_revoked(&AccessToken::from("valid")));
+        assert_eq!(tokens.keys(), vec!["valid"]);
+    }
+
+    #[test]
+    fn test_token_validator_retries() {
+        let tokens = ValidatedTokens::new();
+        tokens.insert("token", AccessToken::from("token"));
+
+        let mut calls = 0;
+        let validator = TokenValidator::builder()
+            .tokens(tokens.clone())
+            .validate(|_request: ValidateTokenRequest| {
+                calls += 1;
+                let status = if calls == 3 { 200 } else { 503 };
+                async move {
+                    if status == 200 {
+                        Ok::<_, ()>(allowed([Scope::ChatRead]))
+                    } else {
+                        Validation::from_response(status, b"").map_err(|_| ())
+                    }
+                }
+            })
+            .retry_delay(Duration::ZERO)
+            .max_retries(2)
+            .build();
+
+        let events = block_on(validator.run().take(1).collect::<Vec<_>>());
+        assert!(matches!(
+            events[..],
+            [ValidationEvent::Valid { key: "token", .. }]
+        ));
+        assert_eq!(calls, 3);
+        assert!(matches!(
+            Validation::from_response(429, b"not json"),
+            Ok(Validation::Unavailable { status: 429 })
+        ));
+    }
+}
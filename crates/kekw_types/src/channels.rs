@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// Returned by [Get Channel Information](https://dev.twitch.tv/docs/api/reference/#get-channel-information).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Channel {
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    /// An ISO 639-1 code, or `other`.
+    pub broadcaster_language: String,
+    /// The category, or empty if none is set.
+    pub game_id: String,
+    pub game_name: String,
+    pub title: String,
+    /// The stream delay in seconds, only visible to the broadcaster.
+    pub delay: u32,
+    pub tags: Vec<String>,
+    pub content_classification_labels: Vec<String>,
+    pub is_branded_content: bool,
+}
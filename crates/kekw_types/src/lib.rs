@@ -1,14 +1,8 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+/// Channels, and the information a broadcaster sets for them.
+pub mod channels;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Live streams.
+pub mod streams;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+/// Users, and users in the context of a channel.
+pub mod users;
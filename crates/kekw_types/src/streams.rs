@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Returned by [Get Streams](https://dev.twitch.tv/docs/api/reference/#get-streams).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stream {
+    pub id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    /// The category, or empty if none is set.
+    pub game_id: String,
+    pub game_name: String,
+    /// `live`, or empty if there was an error.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub viewer_count: u64,
+    pub started_at: String,
+    /// An ISO 639-1 code, or `other`.
+    pub language: String,
+    /// Contains `{width}` and `{height}` placeholders.
+    pub thumbnail_url: String,
+    pub is_mature: bool,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Returned by [Get Users](https://dev.twitch.tv/docs/api/reference/#get-users).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub login: String,
+    pub display_name: String,
+    /// One of `admin`, `global_mod`, `staff`, or empty.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// One of `affiliate`, `partner`, or empty.
+    pub broadcaster_type: String,
+    pub description: String,
+    pub profile_image_url: String,
+    pub offline_image_url: String,
+    /// Only present with the `user:read:email` scope.
+    pub email: Option<String>,
+    pub created_at: String,
+}
+
+/// Returned by [Get Chatters](https://dev.twitch.tv/docs/api/reference/#get-chatters).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Chatter {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+}